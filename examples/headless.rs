@@ -0,0 +1,56 @@
+//! Runs the game without a window, steering the snake randomly,
+//! and prints the final score.
+//!
+//! Usage: `cargo run --release --example headless -- [steps] [seed] [options...]`,
+//! where the options are the same as the game's (e.g. `--walls --foods 3`).
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use snake_rs::{config::*, game::*, primitives::*};
+use std::{env, time::Instant};
+
+/// The number of steps to run if none were given.
+const DEFAULT_STEPS: usize = 10_000;
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let steps = args.next().map_or(DEFAULT_STEPS, |steps| {
+        steps.parse().expect("steps must be a number")
+    });
+    let seed = args
+        .next()
+        .map_or(0, |seed| seed.parse().expect("seed must be a number"));
+
+    let config = GameConfig::from_args(args).expect("the options must be valid");
+
+    let mut game = GameBuilder::from_config(config)
+        .seed(seed)
+        .build()
+        .expect("the game must be playable");
+    // the inputs are random as well, but reproducible with the same seed
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let start = Instant::now();
+    let mut result = StepResult::Moved;
+    let mut step = 0;
+    while step < steps && matches!(result, StepResult::Moved | StepResult::Ate(_)) {
+        // reversing (or repeating the direction) is simply rejected
+        let _ = game.set_direction(*DIRECTIONS.choose(&mut rng).unwrap());
+        result = game.step_logic();
+        step += 1;
+    }
+
+    println!(
+        "{:?} after {} steps ({:?}), final score: {}",
+        result,
+        step,
+        start.elapsed(),
+        game.score()
+    );
+}
@@ -0,0 +1,130 @@
+use crate::{board::*, entities::*, game::*, primitives::*};
+use std::collections::VecDeque;
+
+/// A simple autopilot that steers the snake for demos.
+///
+/// Every tick, it greedily moves player one's snake towards the nearest food (by
+/// Manhattan distance), but only into cells that are free, and it prefers cells from which
+/// there's enough room left for the whole snake so that it doesn't
+/// trap itself. It's far from optimal, but it survives for a good while.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AiController;
+
+impl AiController {
+    /// Picks the direction that the snake should move in next.
+    ///
+    /// Returns `None` if every move is deadly (i.e. the snake is already doomed).
+    pub fn choose(&self, game: &Game) -> Option<Direction> {
+        let board = game.board();
+        let snake = game.snake();
+        // the poison food (and the portals, which it can't plan through)
+        // are avoided just like the snakes
+        let mut blocked: Vec<GridPosition> =
+            game.snakes().iter().flat_map(Snake::segments).collect();
+        blocked.extend(game.poison_food().map(Food::position));
+        for &(entrance, exit) in game.portals() {
+            blocked.extend(&[entrance, exit]);
+        }
+        let head = snake.head_position();
+        // the bonus food is worth the detour, otherwise the nearest food will do
+        let target = game.bonus_food().map_or_else(
+            || {
+                game.foods()
+                    .iter()
+                    .map(Food::position)
+                    .min_by_key(|&food| board.distance(head, food))
+                    .unwrap_or_else(|| game.food().position())
+            },
+            |food| food.position(),
+        );
+
+        // every move that doesn't reverse the snake or kill it right away
+        let moves: Vec<(Direction, GridPosition)> = DIRECTIONS
+            .iter()
+            .filter(|direction| **direction != snake.next_direction().inverse())
+            .filter_map(|direction| {
+                board
+                    .step(head, *direction)
+                    .filter(|position| !board.is_obstacle(*position) && !blocked.contains(position))
+                    .map(|position| (*direction, position))
+            })
+            .collect();
+
+        // if possible, avoid moving into pockets that are too small for the snake
+        let roomy: Vec<(Direction, GridPosition)> = moves
+            .iter()
+            .copied()
+            .filter(|(_, position)| reachable_cells(board, *position, &blocked) >= snake.len())
+            .collect();
+        let candidates = if roomy.is_empty() { moves } else { roomy };
+
+        candidates
+            .into_iter()
+            .min_by_key(|(direction, position)| {
+                // ties keep the current direction, to avoid zig-zagging
+                let turns = *direction != snake.next_direction();
+                (board.distance(*position, target), turns)
+            })
+            .map(|(direction, _)| direction)
+    }
+}
+
+/// Helper function that counts the cells reachable from `start`
+/// without going through obstacles or the `blocked` cells.
+fn reachable_cells(board: &Board, start: GridPosition, blocked: &[GridPosition]) -> usize {
+    let mut visited = vec![false; board.width as usize * board.height as usize];
+    for position in blocked.iter().chain(&board.obstacles) {
+        visited[board.index(*position)] = true;
+    }
+
+    let mut count = 0;
+    let mut queue = VecDeque::new();
+    visited[board.index(start)] = true;
+    queue.push_back(start);
+    while let Some(position) = queue.pop_front() {
+        count += 1;
+        for direction in &DIRECTIONS {
+            if let Some(next) = board.step(position, *direction) {
+                if !visited[board.index(next)] {
+                    visited[board.index(next)] = true;
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::*;
+
+    fn play(config: GameConfig, steps: usize) -> (StepResult, u32) {
+        let mut game = Game::new(&GameConfig { ai: true, ..config });
+        let mut result = StepResult::Moved;
+        for _ in 0..steps {
+            result = game.step_logic();
+            if !matches!(result, StepResult::Moved | StepResult::Ate(_)) {
+                break;
+            }
+        }
+        (result, game.score())
+    }
+
+    #[test]
+    fn autopilot_eats_without_dying() {
+        for edges in &[EdgeBehavior::Wrap, EdgeBehavior::Wall] {
+            let (result, score) = play(
+                GameConfig {
+                    edges: *edges,
+                    seed: Some(1),
+                    ..GameConfig::default()
+                },
+                300,
+            );
+            assert_ne!(result, StepResult::Died);
+            assert!(score > 0);
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use crate::{game::*, primitives::*};
+use ggez::{
+    audio::{SoundSource, Source},
+    Context, GameResult,
+};
+use log::warn;
+use rand::Rng;
+
+/// The volume of the die sound relative to the master volume.
+const DIE_SOUND_WEIGHT: f32 = 0.5;
+/// The volume of the turn sound relative to the master volume (it's
+/// played on every turn, so it's kept subtle).
+const TURN_SOUND_WEIGHT: f32 = 0.3;
+/// How much the master volume changes with each key press.
+pub const VOLUME_STEP: f32 = 0.1;
+/// How far (in either direction) the pitch of the eat sound randomly varies.
+const EAT_PITCH_JITTER: f32 = 0.05;
+/// How much higher the eat sound is for each step of the combo multiplier.
+const EAT_PITCH_PER_COMBO: f32 = 0.08;
+
+/// Returns the pitch (ratio) that the eat sound is played at: slightly off
+/// at random so that it sounds livelier, and higher the longer the `combo`
+/// (see `combo_multiplier`) so that fast streaks sound more exciting.
+pub fn eat_pitch<R: Rng>(rng: &mut R, combo: u32) -> f32 {
+    let jitter = rng.gen_range(-EAT_PITCH_JITTER, EAT_PITCH_JITTER);
+    let ramp = (combo_multiplier(combo) - 1) as f32 * EAT_PITCH_PER_COMBO;
+    1.0 + jitter + ramp
+}
+
+/// Abstracts over anything that can play back a sound effect.
+///
+/// `ggez`'s `Source` is the "real" implementation, but keeping
+/// the playback behind a trait allows us to stub it out in tests.
+pub trait Playback {
+    fn play(&mut self) -> GameResult;
+    fn set_volume(&mut self, volume: f32);
+    fn set_pitch(&mut self, ratio: f32);
+}
+
+impl Playback for Source {
+    fn play(&mut self) -> GameResult {
+        SoundSource::play(self)
+    }
+
+    fn set_volume(&mut self, volume: f32) {
+        SoundSource::set_volume(self, volume)
+    }
+
+    fn set_pitch(&mut self, ratio: f32) {
+        SoundSource::set_pitch(self, ratio)
+    }
+}
+
+/// A sound effect that may or may not be available.
+///
+/// Playing a `Sound` never fails: a sound that couldn't be loaded
+/// is simply skipped, and playback errors are reported and swallowed
+/// so that audio trouble can't take down the game loop.
+pub struct Sound<P = Source> {
+    source: Option<P>,
+}
+
+impl Sound {
+    /// Loads the sound at the given resource path.
+    ///
+    /// If the sound can't be loaded (missing file, no audio device, ...)
+    /// a silent `Sound` is returned instead.
+    pub fn load(ctx: &mut Context, path: &str) -> Self {
+        match Source::new(ctx, path) {
+            Ok(source) => Self::new(Some(source)),
+            Err(error) => {
+                warn!("Failed to load sound `{}`: {}", path, error);
+                Self::new(None)
+            }
+        }
+    }
+}
+
+impl<P: Playback> Sound<P> {
+    pub fn new(source: Option<P>) -> Self {
+        Self { source }
+    }
+
+    /// Plays the sound if it's available.
+    ///
+    /// Returns whether the playback has actually started.
+    pub fn play(&mut self) -> bool {
+        match &mut self.source {
+            Some(source) => match source.play() {
+                Ok(()) => true,
+                Err(error) => {
+                    warn!("Failed to play sound: {}", error);
+                    false
+                }
+            },
+            None => false,
+        }
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        if let Some(source) = &mut self.source {
+            source.set_volume(volume);
+        }
+    }
+
+    /// Sets the pitch (ratio) that the sound is played at from now on.
+    pub fn set_pitch(&mut self, ratio: f32) {
+        if let Some(source) = &mut self.source {
+            source.set_pitch(ratio);
+        }
+    }
+}
+
+/// Contains all the sound effects of the game.
+pub struct Sounds<P = Source> {
+    /// Whether the sound effects are played at all (i.e. not muted).
+    pub enabled: bool,
+    pub eat: Sound<P>,
+    pub die: Sound<P>,
+    /// Played whenever a snake turns.
+    pub turn: Sound<P>,
+    /// Played when a game starts (or restarts).
+    pub start: Sound<P>,
+}
+
+impl Sounds {
+    /// Loads all of the sound effects from the resources.
+    pub fn load(ctx: &mut Context) -> Self {
+        let mut sounds = Self {
+            enabled: true,
+            eat: Sound::load(ctx, "/eat-sound.ogg"),
+            die: Sound::load(ctx, "/die-sound.ogg"),
+            turn: Sound::load(ctx, "/turn-sound.wav"),
+            start: Sound::load(ctx, "/start-sound.wav"),
+        };
+        sounds.set_volume(1.0);
+        sounds
+    }
+}
+
+impl<P: Playback> Sounds<P> {
+    /// Sets the master volume (between 0.0 and 1.0) of all of the sound effects,
+    /// keeping their volumes relative to each other.
+    pub fn set_volume(&mut self, volume: f32) {
+        self.eat.set_volume(volume);
+        self.die.set_volume(volume * DIE_SOUND_WEIGHT);
+        self.turn.set_volume(volume * TURN_SOUND_WEIGHT);
+        self.start.set_volume(volume);
+    }
+
+    /// Plays the turn sound (unless the sounds are muted).
+    ///
+    /// Returns whether the playback has actually started.
+    pub fn play_turn(&mut self) -> bool {
+        self.enabled && self.turn.play()
+    }
+
+    /// Plays the start jingle (unless the sounds are muted).
+    ///
+    /// Returns whether the playback has actually started.
+    pub fn play_start(&mut self) -> bool {
+        self.enabled && self.start.play()
+    }
+
+    /// Plays the sound effect that matches what happened in a step
+    /// of the game (unless the sounds are muted).
+    ///
+    /// The eat sound is played at the given `eat_pitch` (see `eat_pitch`),
+    /// while the die sound is always played at its original pitch.
+    ///
+    /// Returns whether the playback has actually started.
+    pub fn play_for(&mut self, result: StepResult, eat_pitch: f32) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        match result {
+            StepResult::Moved | StepResult::TimeUp => false,
+            StepResult::Ate(_) | StepResult::Won => {
+                self.eat.set_pitch(eat_pitch);
+                self.eat.play()
+            }
+            StepResult::Died
+            | StepResult::PlayerLost(_)
+            | StepResult::Draw
+            | StepResult::Respawned(_) => {
+                self.die.set_pitch(1.0);
+                self.die.play()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ggez::GameError;
+
+    /// A `Playback` that counts how many times it was played,
+    /// and optionally fails every time.
+    #[derive(Default)]
+    struct StubSource {
+        plays: usize,
+        volume: f32,
+        pitch: f32,
+        fail: bool,
+    }
+
+    impl Playback for StubSource {
+        fn play(&mut self) -> GameResult {
+            self.plays += 1;
+            if self.fail {
+                Err(GameError::AudioError("no audio device".to_owned()))
+            } else {
+                Ok(())
+            }
+        }
+
+        fn set_volume(&mut self, volume: f32) {
+            self.volume = volume;
+        }
+
+        fn set_pitch(&mut self, ratio: f32) {
+            self.pitch = ratio;
+        }
+    }
+
+    fn stub_sounds(fail: bool) -> Sounds<StubSource> {
+        let stub = || {
+            Sound::new(Some(StubSource {
+                fail,
+                ..Default::default()
+            }))
+        };
+        Sounds {
+            enabled: true,
+            eat: stub(),
+            die: stub(),
+            turn: stub(),
+            start: stub(),
+        }
+    }
+
+    fn plays(sound: &Sound<StubSource>) -> usize {
+        sound.source.as_ref().map_or(0, |source| source.plays)
+    }
+
+    #[test]
+    fn eating_plays_the_eat_sound() {
+        let mut sounds = stub_sounds(false);
+        assert!(sounds.play_for(StepResult::Ate(1), 1.0));
+        assert_eq!(plays(&sounds.eat), 1);
+        assert_eq!(plays(&sounds.die), 0);
+    }
+
+    #[test]
+    fn dying_plays_the_die_sound() {
+        let mut sounds = stub_sounds(false);
+        assert!(sounds.play_for(StepResult::Died, 1.0));
+        assert_eq!(plays(&sounds.eat), 0);
+        assert_eq!(plays(&sounds.die), 1);
+    }
+
+    #[test]
+    fn moving_plays_nothing() {
+        let mut sounds = stub_sounds(false);
+        assert!(!sounds.play_for(StepResult::Moved, 1.0));
+        assert_eq!(plays(&sounds.eat), 0);
+        assert_eq!(plays(&sounds.die), 0);
+    }
+
+    #[test]
+    fn failing_playback_is_not_fatal() {
+        let mut sounds = stub_sounds(true);
+        assert!(!sounds.play_for(StepResult::Ate(1), 1.0));
+        assert!(!sounds.play_for(StepResult::Died, 1.0));
+        assert_eq!(plays(&sounds.eat), 1);
+        assert_eq!(plays(&sounds.die), 1);
+    }
+
+    #[test]
+    fn muted_sounds_are_not_played() {
+        let mut sounds = stub_sounds(false);
+        sounds.enabled = false;
+        assert!(!sounds.play_for(StepResult::Ate(1), 1.0));
+        assert!(!sounds.play_for(StepResult::Died, 1.0));
+        assert_eq!(plays(&sounds.eat), 0);
+        assert_eq!(plays(&sounds.die), 0);
+    }
+
+    #[test]
+    fn master_volume_keeps_the_die_sound_quieter() {
+        let mut sounds = stub_sounds(false);
+        sounds.set_volume(0.8);
+        let volume = |sound: &Sound<StubSource>| sound.source.as_ref().unwrap().volume;
+        assert_eq!(volume(&sounds.eat), 0.8);
+        assert_eq!(volume(&sounds.die), 0.4);
+    }
+
+    #[test]
+    fn eat_sound_is_played_at_the_given_pitch() {
+        let mut sounds = stub_sounds(false);
+        assert!(sounds.play_for(StepResult::Ate(1), 1.2));
+        assert!(sounds.play_for(StepResult::Died, 1.2));
+        let pitch = |sound: &Sound<StubSource>| sound.source.as_ref().unwrap().pitch;
+        assert_eq!(pitch(&sounds.eat), 1.2);
+        assert_eq!(pitch(&sounds.die), 1.0);
+    }
+
+    #[test]
+    fn eat_pitch_rises_with_the_combo() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            let pitch = eat_pitch(&mut rng, 0);
+            assert!((pitch - 1.0).abs() <= EAT_PITCH_JITTER);
+            let pitch = eat_pitch(&mut rng, MAX_COMBO_MULTIPLIER);
+            assert!(pitch - EAT_PITCH_JITTER > 1.0 + EAT_PITCH_JITTER);
+        }
+    }
+
+    #[test]
+    fn missing_sound_is_skipped() {
+        let mut sounds: Sounds<StubSource> = Sounds {
+            enabled: true,
+            eat: Sound::new(None),
+            die: Sound::new(None),
+            turn: Sound::new(None),
+            start: Sound::new(None),
+        };
+        assert!(!sounds.play_for(StepResult::Ate(1), 1.0));
+        assert!(!sounds.play_for(StepResult::Died, 1.0));
+        assert!(!sounds.play_turn());
+        assert!(!sounds.play_start());
+    }
+
+    #[test]
+    fn turning_and_starting_play_their_sounds() {
+        let mut sounds = stub_sounds(false);
+        assert!(sounds.play_turn());
+        assert!(sounds.play_start());
+        assert_eq!((plays(&sounds.turn), plays(&sounds.start)), (1, 1));
+        sounds.enabled = false;
+        assert!(!sounds.play_turn());
+        assert!(!sounds.play_start());
+        assert_eq!((plays(&sounds.turn), plays(&sounds.start)), (1, 1));
+        assert_eq!(plays(&sounds.eat), 0);
+    }
+}
@@ -0,0 +1,287 @@
+use crate::primitives::*;
+use log::warn;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+/// The smallest size (of each dimension) of a board that
+/// can fit the ring walls layout.
+const MIN_RING_WALLS_SIZE: i16 = 10;
+
+/// Describes the game board that the snake moves on.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Board {
+    pub width: i16,
+    pub height: i16,
+    pub edges: EdgeBehavior,
+    /// The cells that the snake dies on, and that food never spawns on.
+    pub obstacles: Vec<GridPosition>,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Self::new(GRID_SIZE.0, GRID_SIZE.1, EdgeBehavior::Wrap)
+    }
+}
+
+impl Board {
+    pub fn new(width: i16, height: i16, edges: EdgeBehavior) -> Self {
+        Self {
+            width,
+            height,
+            edges,
+            obstacles: Vec::new(),
+        }
+    }
+
+    /// Adds the ring walls layout to the obstacles of the board:
+    /// a ring around the center of the board, with a gap in the
+    /// middle of each of its sides.
+    ///
+    /// Boards that are too small for the layout are left untouched.
+    pub fn add_ring_walls(&mut self) {
+        if self.width < MIN_RING_WALLS_SIZE || self.height < MIN_RING_WALLS_SIZE {
+            warn!(
+                "The board is too small for walls (must be at least {0}x{0})",
+                MIN_RING_WALLS_SIZE
+            );
+            return;
+        }
+        let (left, right) = (self.width / 4, self.width - 1 - self.width / 4);
+        let (top, bottom) = (self.height / 4, self.height - 1 - self.height / 4);
+        let (center_x, center_y) = (self.width / 2, self.height / 2);
+        for x in left..=right {
+            if (x - center_x).abs() > 1 {
+                self.obstacles.push(GridPosition::new(x, top));
+                self.obstacles.push(GridPosition::new(x, bottom));
+            }
+        }
+        for y in top + 1..bottom {
+            if (y - center_y).abs() > 1 {
+                self.obstacles.push(GridPosition::new(left, y));
+                self.obstacles.push(GridPosition::new(right, y));
+            }
+        }
+    }
+
+    /// Returns the default pair of portals: just above the middle of the
+    /// board, next to its left and right edges (which keeps them clear of
+    /// the ring walls, the starting food and the starting snakes).
+    pub fn default_portals(&self) -> (GridPosition, GridPosition) {
+        let y = self.height / 2 - 1;
+        (
+            GridPosition::new(1, y),
+            GridPosition::new(self.width - 2, y),
+        )
+    }
+
+    /// Returns the number of cells that the snake can occupy.
+    pub fn playable_cells(&self) -> usize {
+        self.width as usize * self.height as usize - self.obstacles.len()
+    }
+
+    /// Checks whether the given position is on the board.
+    pub fn contains(&self, position: GridPosition) -> bool {
+        (0..self.width).contains(&position.x) && (0..self.height).contains(&position.y)
+    }
+
+    /// Checks whether there is an obstacle at the given position.
+    pub fn is_obstacle(&self, position: GridPosition) -> bool {
+        self.obstacles.contains(&position)
+    }
+
+    /// The size of the board in terms of how many grid
+    /// cells it takes up.
+    pub fn size(&self) -> (i16, i16) {
+        (self.width, self.height)
+    }
+
+    /// Picks a random cell of the board that isn't in `occupied`
+    /// (or an obstacle), uniformly from all of the free cells.
+    ///
+    /// Returns `None` if there are no free cells left.
+    pub fn random_free_cell<R: Rng>(
+        &self,
+        rng: &mut R,
+        occupied: &[GridPosition],
+    ) -> Option<GridPosition> {
+        let mut is_free = vec![true; self.width as usize * self.height as usize];
+        for position in occupied.iter().chain(&self.obstacles) {
+            is_free[self.index(*position)] = false;
+        }
+        let free_cells: Vec<GridPosition> = self
+            .cells()
+            .filter(|position| is_free[self.index(*position)])
+            .collect();
+        free_cells.choose(rng).copied()
+    }
+
+    /// Returns an iterator over all of the cells of the board (row by row).
+    pub fn cells(&self) -> impl Iterator<Item = GridPosition> {
+        let (width, height) = self.size();
+        (0..height).flat_map(move |y| (0..width).map(move |x| GridPosition::new(x, y)))
+    }
+
+    /// Returns an iterator over the cells just outside of the board, which
+    /// a decorative border around it is drawn on (see `GameConfig::border`).
+    pub fn border_cells(&self) -> impl Iterator<Item = GridPosition> {
+        let (width, height) = self.size();
+        let rows = vec![-1, height]
+            .into_iter()
+            .flat_map(move |y| (-1..=width).map(move |x| GridPosition::new(x, y)));
+        let columns = vec![-1, width]
+            .into_iter()
+            .flat_map(move |x| (0..height).map(move |y| GridPosition::new(x, y)));
+        rows.chain(columns)
+    }
+
+    /// Helper function that converts a position on the board
+    /// to an index into a row-by-row array of its cells.
+    pub(crate) fn index(&self, position: GridPosition) -> usize {
+        position.y as usize * self.width as usize + position.x as usize
+    }
+
+    /// Returns the position reached by moving from `position` in `direction`.
+    ///
+    /// Returns `None` if the move would leave the board, which can only happen
+    /// across an edge that the snake doesn't wrap around (see `EdgeBehavior`).
+    pub fn step(&self, position: GridPosition, direction: Direction) -> Option<GridPosition> {
+        GridPosition::try_move(position, direction, self.size(), self.edges)
+    }
+
+    /// Returns the Manhattan distance between two positions on the
+    /// board, taking wrapping around the edges into account.
+    pub fn distance(&self, from: GridPosition, to: GridPosition) -> i16 {
+        let axis = |from: i16, to: i16, size: i16, wraps: bool| {
+            let distance = (from - to).abs();
+            if wraps {
+                distance.min(size - distance)
+            } else {
+                distance
+            }
+        };
+        axis(from.x, to.x, self.width, self.edges.wraps_x())
+            + axis(from.y, to.y, self.height, self.edges.wraps_y())
+    }
+
+    /// Returns the cell on the opposite edge of the board that a snake whose
+    /// head is at `position` emerges from, if it's going in `direction` and
+    /// is within one cell of wrapping around.
+    ///
+    /// Returns `None` otherwise (which includes `EdgeBehavior::Wall`).
+    pub fn wrap_exit(&self, position: GridPosition, direction: Direction) -> Option<GridPosition> {
+        if self.edges == EdgeBehavior::Wall {
+            return None;
+        }
+        let mut position = position;
+        for _ in 0..2 {
+            let next = self.step(position, direction)?;
+            // a move between neighbouring cells changes a coordinate by 1 at most
+            if (next.x - position.x).abs() > 1 || (next.y - position.y).abs() > 1 {
+                return Some(next);
+            }
+            position = next;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn random_free_cell_skips_occupied_cells() {
+        let board = Board::new(4, 4, EdgeBehavior::Wrap);
+        let free = GridPosition::new(2, 3);
+        let occupied: Vec<GridPosition> = board.cells().filter(|cell| *cell != free).collect();
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..10 {
+            assert_eq!(board.random_free_cell(&mut rng, &occupied), Some(free));
+        }
+    }
+
+    #[test]
+    fn random_free_cell_skips_obstacles() {
+        let mut board = Board::new(4, 4, EdgeBehavior::Wrap);
+        let free = GridPosition::new(1, 1);
+        board.obstacles = board.cells().filter(|cell| *cell != free).collect();
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(board.random_free_cell(&mut rng, &[]), Some(free));
+    }
+
+    #[test]
+    fn distance_wraps_around_the_edges() {
+        let board = Board::new(10, 10, EdgeBehavior::Wrap);
+        assert_eq!(board.distance((0, 0).into(), (9, 9).into()), 2);
+        let board = Board::new(10, 10, EdgeBehavior::Wall);
+        assert_eq!(board.distance((0, 0).into(), (9, 9).into()), 18);
+    }
+
+    #[test]
+    fn ring_walls_leave_the_start_and_center_free() {
+        let mut board = Board::default();
+        board.add_ring_walls();
+        assert!(!board.obstacles.is_empty());
+        assert!(!board.is_obstacle(GridPosition::new(0, 0)));
+        assert!(!board.is_obstacle(GridPosition::new(1, 0)));
+        assert!(!board.is_obstacle(GridPosition::new(board.width / 2, board.height / 2)));
+    }
+
+    #[test]
+    fn random_free_cells_are_deterministic_for_a_seed() {
+        let board = Board::default();
+        let mut first = StdRng::seed_from_u64(42);
+        let mut second = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            assert_eq!(
+                board.random_free_cell(&mut first, &[]),
+                board.random_free_cell(&mut second, &[])
+            );
+        }
+    }
+
+    #[test]
+    fn border_surrounds_the_board() {
+        let board = Board::new(6, 4, EdgeBehavior::Wall);
+        let border: Vec<GridPosition> = board.border_cells().collect();
+        assert_eq!(border.len(), 2 * (6 + 2) + 2 * 4);
+        assert!(border.contains(&GridPosition::new(-1, -1)));
+        assert!(border.contains(&GridPosition::new(6, 4)));
+        assert!(border.contains(&GridPosition::new(-1, 2)));
+        assert!(!border
+            .iter()
+            .any(|cell| board.cells().any(|inside| inside == *cell)));
+    }
+
+    #[test]
+    fn wrap_exit_is_on_the_opposite_edge() {
+        let board = Board::new(10, 8, EdgeBehavior::Wrap);
+        let exit = |x, y, direction| board.wrap_exit(GridPosition::new(x, y), direction);
+        assert_eq!(exit(9, 3, Direction::Right), Some(GridPosition::new(0, 3)));
+        assert_eq!(exit(8, 3, Direction::Right), Some(GridPosition::new(0, 3)));
+        assert_eq!(exit(7, 3, Direction::Right), None);
+        assert_eq!(exit(9, 3, Direction::Left), None);
+        assert_eq!(exit(4, 1, Direction::Up), Some(GridPosition::new(4, 7)));
+        let walled = Board::new(10, 8, EdgeBehavior::Wall);
+        assert_eq!(
+            walled.wrap_exit(GridPosition::new(9, 3), Direction::Right),
+            None
+        );
+    }
+
+    #[test]
+    fn only_cells_inside_are_on_the_board() {
+        let board = Board::new(6, 4, EdgeBehavior::Wrap);
+        assert!(board.cells().all(|cell| board.contains(cell)));
+        assert!(!board.border_cells().any(|cell| board.contains(cell)));
+    }
+
+    #[test]
+    fn random_free_cell_on_a_full_board_is_none() {
+        let board = Board::new(4, 4, EdgeBehavior::Wrap);
+        let occupied: Vec<GridPosition> = board.cells().collect();
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(board.random_free_cell(&mut rng, &occupied), None);
+    }
+}
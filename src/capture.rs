@@ -0,0 +1,80 @@
+use ggez::{
+    filesystem,
+    graphics::{self, ImageFormat},
+    Context, GameResult,
+};
+use std::path::{Path, PathBuf};
+
+/// Dumps the rendered frames to numbered PNGs (see `--record-frames`), e.g.
+/// to assemble them into a clip afterwards.
+///
+/// The frames are saved inside of the user data directory, and only one frame
+/// is saved for each tick of the game (no matter how many frames are drawn),
+/// which keeps the number of files down without missing any moves.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FrameRecorder {
+    /// The directory (inside of the user data directory) that the frames are saved to.
+    dir: PathBuf,
+    /// The number of frames that were saved so far.
+    frames: u32,
+    /// The tick of the last frame that was saved (if any).
+    last_tick: Option<u64>,
+}
+
+impl FrameRecorder {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            // the paths of the user data directory are rooted at `/`
+            dir: Path::new("/").join(dir),
+            frames: 0,
+            last_tick: None,
+        }
+    }
+
+    /// Returns the path that the frame of the given tick should be saved to,
+    /// or `None` if a frame of that tick was already saved.
+    fn next_frame(&mut self, tick: u64) -> Option<PathBuf> {
+        if self.last_tick == Some(tick) {
+            return None;
+        }
+        self.last_tick = Some(tick);
+        self.frames += 1;
+        Some(self.dir.join(format!("frame_{:05}.png", self.frames)))
+    }
+
+    /// Saves the frame that was just presented, if it's the first one of the given tick.
+    pub fn capture(&mut self, ctx: &mut Context, tick: u64) -> GameResult {
+        let path = match self.next_frame(tick) {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if self.frames == 1 {
+            filesystem::create_dir(ctx, &self.dir)?;
+        }
+        graphics::screenshot(ctx)?.encode(ctx, ImageFormat::Png, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_frame_is_saved_for_each_tick() {
+        let mut recorder = FrameRecorder::new(Path::new("frames"));
+        assert_eq!(
+            recorder.next_frame(0),
+            Some(PathBuf::from("/frames/frame_00001.png"))
+        );
+        assert_eq!(recorder.next_frame(0), None);
+        assert_eq!(
+            recorder.next_frame(1),
+            Some(PathBuf::from("/frames/frame_00002.png"))
+        );
+        // a restarted game starts counting its ticks over, but not the frames
+        assert_eq!(
+            recorder.next_frame(0),
+            Some(PathBuf::from("/frames/frame_00003.png"))
+        );
+    }
+}
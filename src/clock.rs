@@ -0,0 +1,163 @@
+use crate::primitives::*;
+use log::trace;
+use std::time::{Duration, Instant};
+
+/// The most updates that are run in a single frame, so that a slow frame
+/// can't fall further and further behind trying to catch up (the rest of
+/// the backlog is dropped instead).
+pub const MAX_UPDATES_PER_FRAME: u32 = 4;
+
+/// Keeps track of the time that the game logic didn't catch up with yet,
+/// so that it runs at a fixed rate no matter how often frames are drawn.
+///
+/// Every frame, the time that passed since the last one is fed to the clock
+/// with `advance`, then updates are taken from it with `tick` for as long as
+/// they're due. Whatever is left over is how far along the game is to the
+/// next update, which is used to interpolate the drawing (see `segment`).
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct UpdateClock {
+    accumulator: Duration,
+}
+
+impl UpdateClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the time that passed since the last frame, for updates of the given
+    /// `interval`, unless the game stalled (see `MAX_STALLED_UPDATES`).
+    pub fn advance(&mut self, elapsed: Duration, interval: Duration) {
+        if is_stalled(elapsed, interval) {
+            // don't make up for the stall, just carry on from where we were
+            trace!("stalled for {:?}", elapsed);
+            return;
+        }
+        self.accumulator = (self.accumulator + elapsed).min(interval * MAX_UPDATES_PER_FRAME);
+    }
+
+    /// Checks whether an update of the given `interval` is due,
+    /// and takes its time off the clock if so.
+    pub fn tick(&mut self, interval: Duration) -> bool {
+        if self.accumulator >= interval {
+            self.accumulator -= interval;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns how far along (between 0.0 and 1.0) the game is
+    /// towards the next update of the given `interval`.
+    pub fn segment(&self, interval: Duration) -> f32 {
+        update_segment(self.accumulator, interval)
+    }
+
+    /// Forgets all of the time that wasn't simulated yet
+    /// (e.g. when a new game starts).
+    pub fn reset(&mut self) {
+        self.accumulator = Duration::default();
+    }
+}
+
+/// Caps the rate that frames are drawn at, so that the game doesn't keep a
+/// CPU core busy drawing far more frames than the screen can show (e.g. when
+/// vsync is off or ignored by the driver).
+///
+/// After every frame, the time until the next one is due is taken from the
+/// limiter with `wait` (and slept for). Falling behind doesn't make the next
+/// frames come any faster, the limiter just carries on from there.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FrameLimiter {
+    /// How long each frame lasts at the capped rate.
+    frame: Duration,
+    /// When the next frame is due (`None` before the first one).
+    next_frame: Option<Instant>,
+}
+
+impl FrameLimiter {
+    /// Creates a `FrameLimiter` for the given frames per second (which can't be 0).
+    pub fn new(fps: u32) -> Self {
+        Self {
+            frame: Duration::from_secs(1) / fps,
+            next_frame: None,
+        }
+    }
+
+    /// Returns how long to wait from `now` until the next frame is due, and
+    /// schedules the one after it.
+    pub fn wait(&mut self, now: Instant) -> Duration {
+        let due = match self.next_frame {
+            Some(next_frame) if next_frame > now => next_frame,
+            _ => now,
+        };
+        self.next_frame = Some(due + self.frame);
+        due - now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    fn ticks(clock: &mut UpdateClock) -> u32 {
+        let mut ticks = 0;
+        while clock.tick(INTERVAL) {
+            ticks += 1;
+        }
+        ticks
+    }
+
+    #[test]
+    fn leftover_time_is_kept_for_the_next_frames() {
+        let mut clock = UpdateClock::new();
+        clock.advance(Duration::from_millis(60), INTERVAL);
+        assert_eq!(ticks(&mut clock), 0);
+        assert!((clock.segment(INTERVAL) - 0.6).abs() < 1e-6);
+        clock.advance(Duration::from_millis(60), INTERVAL);
+        assert_eq!(ticks(&mut clock), 1);
+        assert!((clock.segment(INTERVAL) - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn slow_frames_run_several_updates() {
+        let mut clock = UpdateClock::new();
+        clock.advance(Duration::from_millis(250), INTERVAL);
+        assert_eq!(ticks(&mut clock), 2);
+        assert!((clock.segment(INTERVAL) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn backlog_is_capped() {
+        let mut clock = UpdateClock::new();
+        for _ in 0..10 {
+            clock.advance(INTERVAL * MAX_STALLED_UPDATES, INTERVAL);
+        }
+        assert_eq!(ticks(&mut clock), MAX_UPDATES_PER_FRAME);
+    }
+
+    #[test]
+    fn frames_wait_for_their_turn() {
+        let mut limiter = FrameLimiter::new(50);
+        let start = Instant::now();
+        assert_eq!(limiter.wait(start), Duration::default());
+        assert_eq!(
+            limiter.wait(start + Duration::from_millis(5)),
+            Duration::from_millis(15)
+        );
+        // a slow frame isn't made up for by rushing the next ones
+        let late = start + Duration::from_millis(100);
+        assert_eq!(limiter.wait(late), Duration::default());
+        assert_eq!(limiter.wait(late), Duration::from_millis(20));
+    }
+
+    #[test]
+    fn stalls_are_not_made_up_for() {
+        let mut clock = UpdateClock::new();
+        clock.advance(Duration::from_millis(50), INTERVAL);
+        clock.advance(Duration::from_secs(5), INTERVAL);
+        assert_eq!(ticks(&mut clock), 0);
+        assert_eq!(clock.segment(INTERVAL), 0.5);
+    }
+}
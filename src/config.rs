@@ -0,0 +1,872 @@
+use crate::{board::*, level::*, primitives::*, theme::*};
+use ggez::graphics::{Color, Rect};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+/// The smallest allowed size of each dimension of the game board.
+const MIN_GRID_SIZE: i16 = 4;
+/// The largest allowed size of each dimension of the game board.
+const MAX_GRID_SIZE: i16 = 200;
+/// The smallest allowed pixel size of each tile.
+const MIN_CELL_SIZE: i16 = 8;
+/// The largest allowed pixel size of each tile.
+const MAX_CELL_SIZE: i16 = 256;
+/// The time limit (in seconds) of a time attack game, unless one is given.
+const DEFAULT_TIME_LIMIT: i16 = 60;
+/// The longest allowed time limit (in seconds) of a time attack game.
+const MAX_TIME_LIMIT: i16 = 3600;
+/// The most segments that the snake is allowed to grow by for each piece of food.
+const MAX_GROWTH: i16 = 10;
+/// The most (normal) foods that can be on the board at once.
+const MAX_FOOD_COUNT: i16 = 10;
+/// The most ticks that a snake can be allowed to go without eating (see `--starvation`).
+const MAX_STARVATION: i16 = 10_000;
+/// How long (in milliseconds) a finished game waits before it
+/// restarts with `--auto-restart`, unless another delay is given.
+const DEFAULT_AUTO_RESTART_MILLIS: i16 = 2000;
+/// The longest allowed delay (in milliseconds) of `--auto-restart`.
+const MAX_AUTO_RESTART_MILLIS: i16 = 30_000;
+/// The most frames per second that are drawn, unless another cap is given.
+const DEFAULT_FPS: u32 = 120;
+/// The highest allowed cap of the frames per second.
+const MAX_FPS: i16 = 1000;
+
+/// The runtime configuration of the game.
+///
+/// Defaults to the classic board, and can be
+/// customized from the command line arguments.
+#[derive(Clone, Debug)]
+pub struct GameConfig {
+    /// The width of the game board in grid cells (including the `border`).
+    pub width: i16,
+    /// The height of the game board in grid cells (including the `border`).
+    pub height: i16,
+    /// The pixel size of each tile (which the sprites are scaled to).
+    pub cell_size: (i16, i16),
+    /// What happens when the snake reaches the edge of the board.
+    pub edges: EdgeBehavior,
+    /// The seed of the random number generator.
+    ///
+    /// Setting the seed makes the game deterministic, which
+    /// is useful for debugging and reproducing runs.
+    pub seed: Option<u64>,
+    /// Whether a decorative border of walls is drawn around the board, which takes
+    /// up its outermost cells (so the snakes play inside of it, see `GameConfig::board`).
+    pub border: bool,
+    /// Whether to add the ring walls obstacle layout to the board.
+    pub walls: bool,
+    /// Whether to add a pair of portals to the board (see `Board::default_portals`).
+    pub portals: bool,
+    /// Whether the food wanders around the board (see `FOOD_MOVE_INTERVAL`).
+    pub moving_food: bool,
+    /// The number of (normal) foods on the board at once.
+    pub food_count: usize,
+    /// The most ticks that a snake can go without eating before it starves (if any).
+    pub starvation: Option<u32>,
+    /// Whether food avoids spawning right next to the heads of the
+    /// snakes (see `SAFE_SPAWN_RADIUS`), as long as there's room elsewhere.
+    pub safe_spawn: bool,
+    /// Whether power-ups (see `PowerUp`) spawn every now and then after eating food.
+    pub power_ups: bool,
+    /// Whether the snake is steered by the autopilot (see `AiController`).
+    pub ai: bool,
+    /// Whether a second player plays along (see `Player`).
+    pub two_players: bool,
+    /// The tint of (player one's) snake, which overrides the theme's.
+    pub snake_color: Option<Color>,
+    /// The colors that the game is drawn with.
+    pub theme: Theme,
+    /// Whether the theme was given on the command line, where it
+    /// wins over the one picked in the settings menu (see `Settings`).
+    pub theme_given: bool,
+    /// Whether every other grass tile is tinted darker (see `Theme::grass_at`).
+    pub checkerboard: bool,
+    /// Whether a fading trail is drawn behind the snakes (see `Snake::trail`).
+    pub trail: bool,
+    /// Whether the snakes fade towards their tails (see `Theme::snake_gradient`).
+    pub gradient: bool,
+    /// How the snakes slide between updates.
+    pub easing: Easing,
+    /// The most frames that are drawn per second (see `FrameLimiter`), or 0 for no cap.
+    ///
+    /// The game itself runs at its own rate no matter how many frames are drawn.
+    pub fps: u32,
+    /// Whether the frames are synced to the refresh rate of the screen.
+    pub vsync: bool,
+    /// Whether a held direction key keeps steering the snake (as the
+    /// keyboard repeats it), instead of only the first press counting.
+    pub key_repeat: bool,
+    /// How fast the game is.
+    pub difficulty: Difficulty,
+    /// Whether the difficulty was given on the command line (like `theme_given`).
+    pub difficulty_given: bool,
+    /// How the game speeds up as the snakes grow.
+    pub speed_curve: SpeedCurve,
+    /// The rules that decide when the game ends.
+    pub mode: GameMode,
+    /// The length that the snakes start with, which must fit
+    /// in a row (or a column) of the board.
+    pub start_length: usize,
+    /// The direction that the snakes start moving in.
+    pub start_direction: Direction,
+    /// The number of segments that the snakes grow by for each piece of food.
+    pub growth: usize,
+    /// The recording to replay instead of playing (see `Recording`).
+    pub replay: Option<PathBuf>,
+    /// The directory (inside of the user data directory) that the
+    /// rendered frames are dumped to, if any (see `FrameRecorder`).
+    pub record_frames: Option<PathBuf>,
+    /// How long a finished game waits before it restarts on its own (e.g. for demos),
+    /// or `None` if it waits for the player to restart it.
+    pub auto_restart: Option<Duration>,
+    /// The custom board to play on instead of the default one, which
+    /// decides the size of the board as well (see `Level`).
+    pub level: Option<Level>,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            width: GRID_SIZE.0,
+            height: GRID_SIZE.1,
+            cell_size: GRID_CELL_SIZE,
+            edges: EdgeBehavior::Wrap,
+            seed: None,
+            border: false,
+            walls: false,
+            portals: false,
+            moving_food: false,
+            food_count: 1,
+            starvation: None,
+            safe_spawn: false,
+            power_ups: false,
+            ai: false,
+            two_players: false,
+            snake_color: None,
+            theme: Theme::default(),
+            theme_given: false,
+            checkerboard: false,
+            trail: false,
+            gradient: false,
+            easing: Easing::default(),
+            fps: DEFAULT_FPS,
+            vsync: true,
+            key_repeat: false,
+            difficulty: Difficulty::default(),
+            difficulty_given: false,
+            speed_curve: SpeedCurve::default(),
+            mode: GameMode::default(),
+            start_length: START_LENGTH,
+            start_direction: Direction::Right,
+            growth: GROWTH_PER_FOOD,
+            replay: None,
+            record_frames: None,
+            auto_restart: None,
+            level: None,
+        }
+    }
+}
+
+impl GameConfig {
+    /// Parses the configuration from the command line arguments
+    /// (***without*** the program name).
+    ///
+    /// The supported arguments are:
+    /// * `--width <cells>`: the width of the game board.
+    /// * `--height <cells>`: the height of the game board.
+    /// * `--cell-size <pixels|WxH>`: the pixel size of each tile (which doesn't have to be square).
+    /// * `--edges <wrap|wall|wrap-x|wrap-y>`: whether the snake wraps around or dies
+    ///   at the edges (or wraps around only the left and right / top and bottom ones).
+    /// * `--seed <number>`: the seed of the random number generator.
+    /// * `--border`: draws a border of walls around the board (inside of its size).
+    /// * `--walls`: adds walls to the middle of the board.
+    /// * `--portals`: adds a pair of portals to the board.
+    /// * `--moving-food`: makes the food wander around the board.
+    /// * `--foods <count>`: the number of foods on the board at once.
+    /// * `--starvation <ticks>`: the snake starves if it goes longer than that without eating.
+    /// * `--safe-spawn`: keeps the food from spawning right next to the snakes' heads.
+    /// * `--power-ups`: spawns power-ups every now and then after eating.
+    /// * `--ai`: lets the autopilot play the game.
+    /// * `--two-players`: adds a second snake, for a second player.
+    /// * `--color <RRGGBB>`: tints the snake with the given hex color.
+    /// * `--theme <name>`: the colors that the game is drawn with.
+    /// * `--checkerboard`: tints every other grass tile darker.
+    /// * `--trail`: draws a fading trail behind the snakes.
+    /// * `--gradient`: fades the snakes towards their tails.
+    /// * `--easing <linear|ease-in-out>`: how the snakes slide between updates.
+    /// * `--fps <frames>`: the most frames drawn per second (0 for no cap).
+    /// * `--no-vsync`: doesn't sync the frames to the refresh rate of the screen.
+    /// * `--key-repeat`: keeps steering with a held direction key.
+    /// * `--difficulty <easy|normal|hard>`: how fast the game is.
+    /// * `--speed-curve <linear|logarithmic|stepped>`: how the game speeds up as the snakes grow.
+    /// * `--mode <endless|time-attack|practice>`: the rules that decide when the game ends.
+    /// * `--limit <seconds>`: the time limit of the `time-attack` mode.
+    /// * `--start-length <cells>`: the length that the snakes start with.
+    /// * `--start-direction <up|down|left|right>`: the direction that the snakes start moving in.
+    /// * `--growth <cells>`: the number of segments that each piece of food adds.
+    /// * `--replay <file>`: replays a recorded run.
+    /// * `--record-frames <dir>`: dumps the rendered frames to PNGs in the given directory.
+    /// * `--auto-restart [millis]`: restarts a finished game on its own after the (optional) delay.
+    /// * `--level <file>`: plays on the custom board in the given file (see `Level`).
+    pub fn from_args<I>(args: I) -> SnakeResult<Self>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut config = Self::default();
+        let mut args = args.into_iter().peekable();
+        // the mode and its time limit are combined once all of the arguments are parsed
+        let mut time_attack = false;
+        let mut limit = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--width" => {
+                    config.width = parse_in_range(&arg, args.next(), MIN_GRID_SIZE, MAX_GRID_SIZE)?
+                }
+                "--height" => {
+                    config.height = parse_in_range(&arg, args.next(), MIN_GRID_SIZE, MAX_GRID_SIZE)?
+                }
+                "--cell-size" => config.cell_size = parse_cell_size(&arg, args.next())?,
+                "--edges" => config.edges = required_value(&arg, args.next())?.parse()?,
+                "--seed" => config.seed = Some(parse_number(&arg, args.next())?),
+                "--border" => config.border = true,
+                "--walls" => config.walls = true,
+                "--portals" => config.portals = true,
+                "--moving-food" => config.moving_food = true,
+                "--foods" => {
+                    config.food_count =
+                        parse_in_range(&arg, args.next(), 1, MAX_FOOD_COUNT)? as usize
+                }
+                "--starvation" => {
+                    config.starvation =
+                        Some(parse_in_range(&arg, args.next(), 1, MAX_STARVATION)? as u32)
+                }
+                "--safe-spawn" => config.safe_spawn = true,
+                "--power-ups" => config.power_ups = true,
+                "--ai" => config.ai = true,
+                "--two-players" => config.two_players = true,
+                "--color" => config.snake_color = Some(parse_color(&arg, args.next())?),
+                "--checkerboard" => config.checkerboard = true,
+                "--trail" => config.trail = true,
+                "--gradient" => config.gradient = true,
+                "--easing" => config.easing = required_value(&arg, args.next())?.parse()?,
+                "--fps" => config.fps = parse_in_range(&arg, args.next(), 0, MAX_FPS)? as u32,
+                "--no-vsync" => config.vsync = false,
+                "--key-repeat" => config.key_repeat = true,
+                "--theme" => {
+                    config.theme = required_value(&arg, args.next())?.parse()?;
+                    config.theme_given = true;
+                }
+                "--difficulty" => {
+                    config.difficulty = required_value(&arg, args.next())?.parse()?;
+                    config.difficulty_given = true;
+                }
+                "--speed-curve" => {
+                    config.speed_curve = required_value(&arg, args.next())?.parse()?
+                }
+                "--mode" => {
+                    time_attack = match required_value(&arg, args.next())?.as_str() {
+                        "endless" => {
+                            config.mode = GameMode::Endless;
+                            false
+                        }
+                        "practice" => {
+                            config.mode = GameMode::Practice;
+                            false
+                        }
+                        "time-attack" => true,
+                        mode => {
+                            return Err(SnakeError::ArgumentError(format!(
+                            "Unknown mode `{}` (expected `endless`, `time-attack` or `practice`)",
+                            mode
+                        )))
+                        }
+                    }
+                }
+                "--limit" => limit = Some(parse_in_range(&arg, args.next(), 1, MAX_TIME_LIMIT)?),
+                "--start-length" => {
+                    let length =
+                        parse_in_range(&arg, args.next(), START_LENGTH as i16, MAX_GRID_SIZE)?;
+                    config.start_length = length as usize;
+                }
+                "--start-direction" => {
+                    config.start_direction = required_value(&arg, args.next())?.parse()?
+                }
+                "--growth" => {
+                    config.growth = parse_in_range(&arg, args.next(), 1, MAX_GROWTH)? as usize
+                }
+                "--replay" => config.replay = Some(required_value(&arg, args.next())?.into()),
+                "--auto-restart" => {
+                    // the delay is optional, so only a number right after it is taken as one
+                    let millis = match args.peek().map(|value| value.parse::<i16>()) {
+                        Some(Ok(_)) => {
+                            parse_in_range(&arg, args.next(), 0, MAX_AUTO_RESTART_MILLIS)?
+                        }
+                        _ => DEFAULT_AUTO_RESTART_MILLIS,
+                    };
+                    config.auto_restart = Some(Duration::from_millis(millis as u64));
+                }
+                "--record-frames" => {
+                    config.record_frames = Some(required_value(&arg, args.next())?.into())
+                }
+                "--level" => {
+                    let path = required_value(&arg, args.next())?;
+                    config.level = Some(Level::load_from(Path::new(&path))?);
+                }
+                _ => {
+                    return Err(SnakeError::ArgumentError(format!(
+                        "Unknown argument `{}`",
+                        arg
+                    )))
+                }
+            }
+        }
+        match (time_attack, limit) {
+            (true, limit) => {
+                let limit = limit.unwrap_or(DEFAULT_TIME_LIMIT) as u64;
+                config.mode = GameMode::TimeAttack {
+                    limit: Duration::from_secs(limit),
+                };
+            }
+            (false, Some(_)) => {
+                return Err(SnakeError::ArgumentError(
+                    "`--limit` can only be given with `--mode time-attack`".to_owned(),
+                ))
+            }
+            (false, None) => {}
+        }
+        // a level decides the size of the board (which a border goes around)
+        if let Some(level) = &config.level {
+            let border = if config.border { 2 } else { 0 };
+            config.width = level.width + border;
+            config.height = level.height + border;
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Makes sure that the configuration describes a playable game
+    /// (e.g. that the snakes fit on the board), which `from_args`
+    /// and `GameBuilder::build` already do.
+    pub fn validate(&self) -> SnakeResult {
+        for (dimension, size) in [("width", self.width), ("height", self.height)] {
+            if !(MIN_GRID_SIZE..=MAX_GRID_SIZE).contains(&size) {
+                return Err(SnakeError::ArgumentError(format!(
+                    "The {} of the board must be between {} and {}, got `{}`",
+                    dimension, MIN_GRID_SIZE, MAX_GRID_SIZE, size
+                )));
+            }
+        }
+        let (width, height) = self.board_size();
+        if self.border && (width < MIN_GRID_SIZE || height < MIN_GRID_SIZE) {
+            return Err(SnakeError::ArgumentError(format!(
+                "The board must be at least {0}x{0} inside of its border, got {1}x{2}",
+                MIN_GRID_SIZE, width, height
+            )));
+        }
+        // the snakes start in a single row or column (see `Snake::new`)
+        let (dimension, size) = match self.start_direction {
+            Direction::Left | Direction::Right => ("width", width),
+            Direction::Up | Direction::Down => ("height", height),
+        };
+        if self.start_length < START_LENGTH || self.start_length > size as usize {
+            return Err(SnakeError::ArgumentError(format!(
+                "The start length must be at least {} and fit in the {} of the board ({}), got `{}`",
+                START_LENGTH, dimension, size, self.start_length
+            )));
+        }
+        if let Some(level) = &self.level {
+            if (level.width, level.height) != (width, height) {
+                return Err(SnakeError::ArgumentError(format!(
+                    "The level is {}x{}, but the board is {}x{}",
+                    level.width, level.height, width, height
+                )));
+            }
+            if self.two_players {
+                return Err(SnakeError::ArgumentError(
+                    "A level only has room for a single player".to_owned(),
+                ));
+            }
+            if !level.fits_snake(self.start_direction, self.start_length) {
+                return Err(SnakeError::ArgumentError(format!(
+                    "The snake (of length {}) doesn't fit behind the start of the level",
+                    self.start_length
+                )));
+            }
+        }
+        if !(1..=MAX_GROWTH as usize).contains(&self.growth) {
+            return Err(SnakeError::ArgumentError(format!(
+                "The growth must be between 1 and {}, got `{}`",
+                MAX_GROWTH, self.growth
+            )));
+        }
+        if !(1..=MAX_FOOD_COUNT as usize).contains(&self.food_count) {
+            return Err(SnakeError::ArgumentError(format!(
+                "The number of foods must be between 1 and {}, got `{}`",
+                MAX_FOOD_COUNT, self.food_count
+            )));
+        }
+        if let Some(starvation) = self.starvation {
+            if !(1..=MAX_STARVATION as u32).contains(&starvation) {
+                return Err(SnakeError::ArgumentError(format!(
+                    "The starvation must be between 1 and {} ticks, got `{}`",
+                    MAX_STARVATION, starvation
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The size of the `Board` that the snakes play on, which
+    /// is smaller than the whole board with a `border`.
+    pub fn board_size(&self) -> (i16, i16) {
+        if self.border {
+            (self.width - 2, self.height - 2)
+        } else {
+            (self.width, self.height)
+        }
+    }
+
+    /// How far (in pixels) the `Board` that the snakes play on is from
+    /// the top-left corner of the screen, which leaves room for the `border`.
+    pub fn board_offset(&self) -> (f32, f32) {
+        if self.border {
+            (self.cell_size.0 as f32, self.cell_size.1 as f32)
+        } else {
+            (0.0, 0.0)
+        }
+    }
+
+    /// Creates the `Board` described by this configuration.
+    pub fn board(&self) -> Board {
+        let (width, height) = self.board_size();
+        let mut board = Board::new(width, height, self.edges);
+        if self.walls {
+            board.add_ring_walls();
+        }
+        if let Some(level) = &self.level {
+            board.obstacles.extend(level.obstacles.iter().copied());
+        }
+        board
+    }
+
+    /// The size of the window.
+    pub fn screen_size(&self) -> (f32, f32) {
+        (
+            self.width as f32 * self.cell_size.0 as f32,
+            self.height as f32 * self.cell_size.1 as f32,
+        )
+    }
+
+    /// The screen coordinates that fit the whole game into a window of
+    /// the given size (e.g. when it's fullscreen), without stretching it.
+    ///
+    /// The game is scaled uniformly and centered, and the leftover
+    /// space is letterboxed on either the sides or the top and bottom.
+    pub fn screen_coordinates(&self, window_size: (f32, f32)) -> Rect {
+        let screen_size = self.screen_size();
+        let scale = (window_size.0 / screen_size.0).min(window_size.1 / screen_size.1);
+        let (width, height) = (window_size.0 / scale, window_size.1 / scale);
+        Rect::new(
+            (screen_size.0 - width) / 2.0,
+            (screen_size.1 - height) / 2.0,
+            width,
+            height,
+        )
+    }
+}
+
+/// Helper function that makes sure that an argument was given a value.
+fn required_value(arg: &str, value: Option<String>) -> SnakeResult<String> {
+    value.ok_or_else(|| SnakeError::ArgumentError(format!("Missing value for `{}`", arg)))
+}
+
+/// Helper function that parses the value of a numeric argument.
+fn parse_number<T: FromStr>(arg: &str, value: Option<String>) -> SnakeResult<T> {
+    let value = required_value(arg, value)?;
+    value.parse().map_err(|_| {
+        SnakeError::ArgumentError(format!("`{}` must be a number, got `{}`", arg, value))
+    })
+}
+
+/// Helper function that parses the value of a numeric argument
+/// that must be between `min` and `max` (inclusive).
+fn parse_in_range(arg: &str, value: Option<String>, min: i16, max: i16) -> SnakeResult<i16> {
+    let value = required_value(arg, value)?;
+    match value.parse() {
+        Ok(number) if (min..=max).contains(&number) => Ok(number),
+        _ => Err(SnakeError::ArgumentError(format!(
+            "`{}` must be a number between {} and {}, got `{}`",
+            arg, min, max, value
+        ))),
+    }
+}
+
+/// Helper function that parses the value of the cell size argument, which is
+/// either a single size (for square tiles) or a `WxH` pair of sizes.
+fn parse_cell_size(arg: &str, value: Option<String>) -> SnakeResult<(i16, i16)> {
+    let value = required_value(arg, value)?;
+    let (width, height) = match value.split_once(['x', 'X']) {
+        Some((width, height)) => (width, height),
+        None => (value.as_str(), value.as_str()),
+    };
+    match (width.parse(), height.parse()) {
+        (Ok(width), Ok(height))
+            if (MIN_CELL_SIZE..=MAX_CELL_SIZE).contains(&width)
+                && (MIN_CELL_SIZE..=MAX_CELL_SIZE).contains(&height) =>
+        {
+            Ok((width, height))
+        }
+        _ => Err(SnakeError::ArgumentError(format!(
+            "`{}` must be a size (or a `WxH` pair of sizes) between {} and {}, got `{}`",
+            arg, MIN_CELL_SIZE, MAX_CELL_SIZE, value
+        ))),
+    }
+}
+
+/// Helper function that parses the value of a hex color argument
+/// (`RRGGBB`, optionally prefixed with a `#`).
+fn parse_color(arg: &str, value: Option<String>) -> SnakeResult<Color> {
+    let value = required_value(arg, value)?;
+    let hex = value.trim_start_matches('#');
+    let is_hex = hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit());
+    match u32::from_str_radix(hex, 16) {
+        Ok(rgb) if is_hex => Ok(Color::from_rgb_u32(rgb)),
+        _ => Err(SnakeError::ArgumentError(format!(
+            "`{}` must be a hex color (RRGGBB), got `{}`",
+            arg, value
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> SnakeResult<GameConfig> {
+        GameConfig::from_args(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_is_the_default_board() {
+        let config = parse(&[]).unwrap();
+        assert_eq!(config.board().size(), GRID_SIZE);
+        assert_eq!(config.edges, EdgeBehavior::Wrap);
+    }
+
+    #[test]
+    fn width_and_height_are_parsed() {
+        let config = parse(&["--width", "40", "--height", "25"]).unwrap();
+        assert_eq!(config.board().size(), (40, 25));
+    }
+
+    #[test]
+    fn cell_size_scales_the_screen() {
+        let config = parse(&["--cell-size", "48"]).unwrap();
+        assert_eq!(config.cell_size, (48, 48));
+        assert_eq!(config.screen_size(), (30.0 * 48.0, 20.0 * 48.0));
+        assert!(parse(&["--cell-size", "0"]).is_err());
+        assert!(parse(&["--cell-size", "-32"]).is_err());
+        assert!(parse(&["--cell-size", "1000"]).is_err());
+    }
+
+    #[test]
+    fn border_shrinks_the_board_but_not_the_screen() {
+        let config = parse(&["--border", "--width", "12"]).unwrap();
+        assert_eq!(config.board().size(), (10, 18));
+        assert_eq!(config.board_offset(), (32.0, 32.0));
+        assert_eq!(config.screen_size(), (12.0 * 32.0, 20.0 * 32.0));
+        assert_eq!(parse(&[]).unwrap().board_offset(), (0.0, 0.0));
+        assert!(parse(&["--border", "--width", "5"]).is_err());
+        assert!(parse(&["--border", "--width", "11", "--start-length", "10"]).is_err());
+    }
+
+    #[test]
+    fn cell_size_can_be_rectangular() {
+        let config = parse(&["--cell-size", "48x24"]).unwrap();
+        assert_eq!(config.cell_size, (48, 24));
+        assert_eq!(config.screen_size(), (30.0 * 48.0, 20.0 * 24.0));
+        assert!(parse(&["--cell-size", "48x"]).is_err());
+        assert!(parse(&["--cell-size", "48x4"]).is_err());
+        assert!(parse(&["--cell-size", "48x24x12"]).is_err());
+    }
+
+    #[test]
+    fn wider_windows_are_letterboxed_on_the_sides() {
+        let config = GameConfig::default();
+        let (width, height) = config.screen_size();
+        assert_eq!(
+            config.screen_coordinates((width, height)),
+            Rect::new(0.0, 0.0, width, height)
+        );
+        assert_eq!(
+            config.screen_coordinates((width * 4.0, height * 2.0)),
+            Rect::new(-width / 2.0, 0.0, width * 2.0, height)
+        );
+        assert_eq!(
+            config.screen_coordinates((width, height * 2.0)),
+            Rect::new(0.0, -height / 2.0, width, height * 2.0)
+        );
+    }
+
+    #[test]
+    fn snake_color_is_parsed() {
+        assert_eq!(parse(&[]).unwrap().snake_color, None);
+        let color = Color::from_rgb(0xff, 0x80, 0x00);
+        assert_eq!(
+            parse(&["--color", "ff8000"]).unwrap().snake_color,
+            Some(color)
+        );
+        assert_eq!(
+            parse(&["--color", "#FF8000"]).unwrap().snake_color,
+            Some(color)
+        );
+        assert!(parse(&["--color", "orange"]).is_err());
+        assert!(parse(&["--color", "fff"]).is_err());
+        assert!(parse(&["--color", "+f8000"]).is_err());
+    }
+
+    #[test]
+    fn theme_is_parsed() {
+        assert_eq!(parse(&[]).unwrap().theme, Theme::default());
+        assert!(!parse(&[]).unwrap().theme_given);
+        assert_eq!(parse(&["--theme", "dark"]).unwrap().theme.name, "dark");
+        assert!(parse(&["--theme", "pink"]).is_err());
+        // even the default theme counts as given
+        let default = Theme::default().name;
+        assert!(parse(&["--theme", default]).unwrap().theme_given);
+    }
+
+    #[test]
+    fn difficulty_is_parsed() {
+        assert_eq!(parse(&[]).unwrap().difficulty, Difficulty::Normal);
+        let config = parse(&["--difficulty", "hard"]).unwrap();
+        assert_eq!(config.difficulty, Difficulty::Hard);
+        assert!(config.difficulty_given && !config.theme_given);
+        assert!(parse(&["--difficulty", "normal"]).unwrap().difficulty_given);
+        assert!(parse(&["--difficulty", "insane"]).is_err());
+    }
+
+    #[test]
+    fn speed_curve_is_parsed() {
+        assert_eq!(parse(&[]).unwrap().speed_curve, SpeedCurve::Linear);
+        let config = parse(&["--speed-curve", "logarithmic"]).unwrap();
+        assert_eq!(config.speed_curve, SpeedCurve::Logarithmic);
+        assert!(parse(&["--speed-curve", "cubic"]).is_err());
+    }
+
+    #[test]
+    fn start_length_must_fit_on_the_board() {
+        assert_eq!(parse(&[]).unwrap().start_length, START_LENGTH);
+        assert_eq!(parse(&["--start-length", "5"]).unwrap().start_length, 5);
+        assert!(parse(&["--start-length", "1"]).is_err());
+        assert!(parse(&["--start-length", "31"]).is_err());
+        assert!(parse(&["--width", "40", "--start-length", "31"]).is_ok());
+    }
+
+    #[test]
+    fn growth_is_parsed() {
+        assert_eq!(parse(&[]).unwrap().growth, GROWTH_PER_FOOD);
+        assert_eq!(parse(&["--growth", "3"]).unwrap().growth, 3);
+        assert!(parse(&["--growth", "0"]).is_err());
+        assert!(parse(&["--growth", "11"]).is_err());
+    }
+
+    #[test]
+    fn food_count_is_parsed() {
+        assert_eq!(parse(&[]).unwrap().food_count, 1);
+        assert_eq!(parse(&["--foods", "3"]).unwrap().food_count, 3);
+        assert!(parse(&["--foods", "0"]).is_err());
+        assert!(parse(&["--foods", "11"]).is_err());
+    }
+
+    #[test]
+    fn starvation_is_parsed() {
+        assert_eq!(parse(&[]).unwrap().starvation, None);
+        assert_eq!(parse(&["--starvation", "50"]).unwrap().starvation, Some(50));
+        assert!(parse(&["--starvation", "0"]).is_err());
+        assert!(parse(&["--starvation"]).is_err());
+    }
+
+    #[test]
+    fn start_direction_is_parsed() {
+        assert_eq!(parse(&[]).unwrap().start_direction, Direction::Right);
+        let config = parse(&["--start-direction", "up"]).unwrap();
+        assert_eq!(config.start_direction, Direction::Up);
+        assert!(parse(&["--start-direction", "north"]).is_err());
+        // a vertical snake has to fit in the height of the board instead
+        assert!(parse(&["--start-length", "25", "--start-direction", "up"]).is_err());
+        assert!(parse(&["--start-length", "25", "--start-direction", "left"]).is_ok());
+    }
+
+    #[test]
+    fn level_decides_the_size_of_the_board() {
+        let path =
+            std::env::temp_dir().join(format!("snake-rs-test-{}-level.txt", std::process::id()));
+        std::fs::write(&path, "........\n.S......\n........\n......#.\n").unwrap();
+        let path = path.to_str().unwrap();
+        let config = parse(&["--level", path]).unwrap();
+        assert_eq!((config.width, config.height), (8, 4));
+        assert!(config.board().is_obstacle(GridPosition::new(6, 3)));
+        let config = parse(&["--level", path, "--border"]).unwrap();
+        assert_eq!((config.width, config.height), (10, 6));
+        assert!(parse(&["--level", path, "--two-players"]).is_err());
+        assert!(parse(&["--level", path, "--start-length", "3"]).is_err());
+        assert!(parse(&["--level", "/nonexistent/level.txt"]).is_err());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn replay_is_parsed() {
+        assert_eq!(parse(&[]).unwrap().replay, None);
+        let config = parse(&["--replay", "run.json"]).unwrap();
+        assert_eq!(config.replay, Some(PathBuf::from("run.json")));
+        assert!(parse(&["--replay"]).is_err());
+    }
+
+    #[test]
+    fn auto_restart_is_parsed_with_its_optional_delay() {
+        assert_eq!(parse(&[]).unwrap().auto_restart, None);
+        let config = parse(&["--auto-restart", "500"]).unwrap();
+        assert_eq!(config.auto_restart, Some(Duration::from_millis(500)));
+        let config = parse(&["--auto-restart", "--ai"]).unwrap();
+        assert_eq!(
+            config.auto_restart,
+            Some(Duration::from_millis(DEFAULT_AUTO_RESTART_MILLIS as u64))
+        );
+        assert!(config.ai);
+        assert!(parse(&["--auto-restart"]).unwrap().auto_restart.is_some());
+        assert!(parse(&["--auto-restart", "-1"]).is_err());
+        assert!(parse(&["--auto-restart", "30001"]).is_err());
+    }
+
+    #[test]
+    fn record_frames_is_parsed() {
+        assert_eq!(parse(&[]).unwrap().record_frames, None);
+        let config = parse(&["--record-frames", "frames"]).unwrap();
+        assert_eq!(config.record_frames, Some(PathBuf::from("frames")));
+        assert!(parse(&["--record-frames"]).is_err());
+    }
+
+    #[test]
+    fn mode_is_parsed_with_its_limit() {
+        assert_eq!(parse(&[]).unwrap().mode, GameMode::Endless);
+        let minute = GameMode::TimeAttack {
+            limit: Duration::from_secs(60),
+        };
+        assert_eq!(parse(&["--mode", "time-attack"]).unwrap().mode, minute);
+        let config = parse(&["--limit", "90", "--mode", "time-attack"]).unwrap();
+        assert_eq!(
+            config.mode,
+            GameMode::TimeAttack {
+                limit: Duration::from_secs(90)
+            }
+        );
+        assert_eq!(
+            parse(&["--mode", "practice"]).unwrap().mode,
+            GameMode::Practice
+        );
+        assert!(parse(&["--mode", "zen"]).is_err());
+        assert!(parse(&["--mode", "practice", "--limit", "90"]).is_err());
+        assert!(parse(&["--limit", "90"]).is_err());
+        assert!(parse(&["--mode", "time-attack", "--limit", "0"]).is_err());
+    }
+
+    #[test]
+    fn edge_behavior_is_parsed() {
+        let config = parse(&["--edges", "wall"]).unwrap();
+        assert_eq!(config.edges, EdgeBehavior::Wall);
+    }
+
+    #[test]
+    fn seed_is_parsed() {
+        assert_eq!(parse(&[]).unwrap().seed, None);
+        assert_eq!(parse(&["--seed", "1234"]).unwrap().seed, Some(1234));
+        assert!(parse(&["--seed", "-1"]).is_err());
+    }
+
+    #[test]
+    fn ai_is_off_unless_asked_for() {
+        assert!(!parse(&[]).unwrap().ai);
+        assert!(parse(&["--ai"]).unwrap().ai);
+    }
+
+    #[test]
+    fn portals_are_parsed() {
+        assert!(!parse(&[]).unwrap().portals);
+        assert!(parse(&["--portals"]).unwrap().portals);
+    }
+
+    #[test]
+    fn moving_food_is_parsed() {
+        assert!(!parse(&[]).unwrap().moving_food);
+        assert!(parse(&["--moving-food"]).unwrap().moving_food);
+    }
+
+    #[test]
+    fn safe_spawn_is_parsed() {
+        assert!(!parse(&[]).unwrap().safe_spawn);
+        assert!(parse(&["--safe-spawn"]).unwrap().safe_spawn);
+    }
+
+    #[test]
+    fn power_ups_is_parsed() {
+        assert!(!parse(&[]).unwrap().power_ups);
+        assert!(parse(&["--power-ups"]).unwrap().power_ups);
+    }
+
+    #[test]
+    fn checkerboard_is_parsed() {
+        assert!(!parse(&[]).unwrap().checkerboard);
+        assert!(parse(&["--checkerboard"]).unwrap().checkerboard);
+    }
+
+    #[test]
+    fn trail_is_parsed() {
+        assert!(!parse(&[]).unwrap().trail);
+        assert!(parse(&["--trail"]).unwrap().trail);
+        assert!(!parse(&[]).unwrap().gradient);
+        assert!(parse(&["--gradient"]).unwrap().gradient);
+    }
+
+    #[test]
+    fn easing_is_parsed() {
+        assert_eq!(parse(&[]).unwrap().easing, Easing::Linear);
+        let config = parse(&["--easing", "ease-in-out"]).unwrap();
+        assert_eq!(config.easing, Easing::EaseInOut);
+        assert!(parse(&["--easing", "bounce"]).is_err());
+    }
+
+    #[test]
+    fn frame_rate_is_parsed() {
+        let config = parse(&[]).unwrap();
+        assert_eq!((config.fps, config.vsync), (DEFAULT_FPS, true));
+        let config = parse(&["--fps", "0", "--no-vsync"]).unwrap();
+        assert_eq!((config.fps, config.vsync), (0, false));
+        assert_eq!(parse(&["--fps", "30"]).unwrap().fps, 30);
+        assert!(parse(&["--fps", "-1"]).is_err());
+    }
+
+    #[test]
+    fn key_repeat_is_parsed() {
+        assert!(!parse(&[]).unwrap().key_repeat);
+        assert!(parse(&["--key-repeat"]).unwrap().key_repeat);
+    }
+
+    #[test]
+    fn two_players_are_parsed() {
+        assert!(!parse(&[]).unwrap().two_players);
+        assert!(parse(&["--two-players"]).unwrap().two_players);
+    }
+
+    #[test]
+    fn invalid_arguments_are_rejected() {
+        assert!(parse(&["--width"]).is_err());
+        assert!(parse(&["--width", "wide"]).is_err());
+        assert!(parse(&["--height", "1"]).is_err());
+        assert!(parse(&["--edges", "bounce"]).is_err());
+        assert!(parse(&["--depth", "10"]).is_err());
+    }
+}
@@ -1,162 +1,594 @@
-use crate::primitives::*;
+use crate::{board::*, primitives::*, theme::*};
 use ggez::{
-    graphics::{self, DrawParam, Image},
+    graphics::{self, Color, DrawParam, Image},
     Context, GameResult,
 };
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 /// Contains all the information needed to describe
 /// the state of the snake itself.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Snake {
     head: PositionedSprite,
     body: VecDeque<PositionedSprite>,
     tail: PositionedSprite,
     direction: Direction,
-    previous_direction: Direction,
-    next_direction: Option<Direction>,
+    /// The turns that were requested but not applied yet,
+    /// one of which is applied every update.
+    queued_directions: VecDeque<Direction>,
+    /// The segments that the `Snake` still has to grow by (see `Snake::grow`),
+    /// one of which is grown every update by leaving the tail in place.
+    #[serde(default)]
+    pending_growth: usize,
+    /// The last `TRAIL_LENGTH` cells that the head was on, newest first.
+    #[serde(default)]
+    trail: VecDeque<GridPosition>,
+    /// How many parts of the `Snake` are on each cell of the board (row by row),
+    /// kept in step as it moves so that `Snake::is_occupied` is O(1).
+    ///
+    /// The parts themselves are the source of truth, so this isn't saved (see
+    /// `Snake::track_occupancy`), and it counts rather than flags the cells
+    /// since the head can run into the rest of the `Snake`.
+    #[serde(skip)]
+    occupancy: Vec<u8>,
+    /// The width of the board that `occupancy` covers.
+    #[serde(skip)]
+    board_width: i16,
+    /// Whether the `Snake` can't crash into itself or the walls (see `PowerUp::Shield`).
+    #[serde(skip)]
+    shielded: bool,
+    /// Whether the shield has just worn off, which still protects the `Snake` for one
+    /// more update, so that it can get out of itself (or a wall) before it counts.
+    #[serde(skip)]
+    shield_grace: bool,
 }
 
 impl Snake {
-    /// Creates a new `Snake` with one head segment at the
-    /// given position and one `Tail` segment behind it (direction is right).
-    pub fn new(position: GridPosition) -> Self {
-        let head = PositionedSprite::new(Sprite::Head(Direction::Right), position);
-        let body = VecDeque::new();
-        let tail = PositionedSprite::new(
-            Sprite::Tail(Direction::Right),
-            GridPosition::new_from_move(position, Direction::Left),
-        );
-        Self {
+    /// Creates a new, straight `Snake` of the given length (which is
+    /// at least `START_LENGTH`), with its head at the given position,
+    /// moving in `direction`, and the rest of it behind it.
+    ///
+    /// The body and the tail wrap around the edges of the `Board`.
+    pub fn new(position: GridPosition, direction: Direction, length: usize, board: &Board) -> Self {
+        let head = PositionedSprite::new(Sprite::Head(direction), position);
+        let behind = direction.inverse();
+        let mut body = VecDeque::new();
+        let mut position = GridPosition::new_from_move(position, behind, board.size());
+        for _ in START_LENGTH..length {
+            body.push_back(PositionedSprite::new(
+                Sprite::Segment(behind, direction),
+                position,
+            ));
+            position = GridPosition::new_from_move(position, behind, board.size());
+        }
+        let tail = PositionedSprite::new(Sprite::Tail(direction), position);
+        let mut snake = Self {
             head,
             body,
             tail,
-            direction: Direction::Right,
-            previous_direction: Direction::Right,
-            next_direction: None,
+            direction,
+            queued_directions: VecDeque::with_capacity(MAX_QUEUED_DIRECTIONS),
+            pending_growth: 0,
+            trail: VecDeque::with_capacity(TRAIL_LENGTH),
+            occupancy: Vec::new(),
+            board_width: 0,
+            shielded: false,
+            shield_grace: false,
+        };
+        snake.track_occupancy(board);
+        snake
+    }
+
+    /// Starts the `Snake` over at the given position, moving in `direction`,
+    /// as short as it gets (`START_LENGTH`), as if it was just created.
+    pub fn reset_to(&mut self, position: GridPosition, direction: Direction, board: &Board) {
+        *self = Self::new(position, direction, START_LENGTH, board);
+    }
+
+    /// Starts keeping track of the cells of the given `Board` that the `Snake`
+    /// is on (see `Snake::is_occupied`), which a `Snake` that was loaded
+    /// (rather than created with `Snake::new`) doesn't do yet.
+    pub fn track_occupancy(&mut self, board: &Board) {
+        self.board_width = board.width;
+        self.occupancy = vec![0; board.width as usize * board.height as usize];
+        for position in self.occupied_cells() {
+            self.occupancy[board.index(position)] += 1;
         }
     }
 
-    /// Sets the direction of the snake.
+    /// Checks that a `Snake` that was loaded (rather than created with `Snake::new`)
+    /// can be played on the given `Board`: every part of it is on the board, next to
+    /// the part before it (or across one of the `portals`), and has a valid sprite.
     ///
-    /// If the direction is invalid, returns a `SnakeError::LogicError`.
-    pub fn set_direction(&mut self, direction: Direction) -> SnakeResult<()> {
-        let is_valid = self.direction.inverse() != direction;
-        if is_valid {
-            if self.previous_direction == self.direction {
-                self.direction = direction;
+    /// Returns a `SnakeError::SaveError` otherwise.
+    pub fn validate(&self, board: &Board, portals: &[(GridPosition, GridPosition)]) -> SnakeResult {
+        let valid_sprites = matches!(self.head.sprite, Sprite::Head(_))
+            && matches!(self.tail.sprite, Sprite::Tail(_))
+            && self.body.iter().all(
+                |segment| matches!(segment.sprite, Sprite::Segment(start, end) if start != end),
+            );
+        if !valid_sprites {
+            return Err(SnakeError::SaveError(
+                "The snake has an invalid sprite".to_owned(),
+            ));
+        }
+        // from the head to the tail
+        let parts: Vec<GridPosition> = std::iter::once(self.head.position)
+            .chain(self.body.iter().map(|segment| segment.position))
+            .chain(std::iter::once(self.tail.position))
+            .collect();
+        if let Some(part) = parts.iter().find(|part| !board.contains(**part)) {
+            return Err(SnakeError::SaveError(format!(
+                "The snake is off the board at ({}, {})",
+                part.x, part.y
+            )));
+        }
+        // the body always wraps around the edges (see `Snake::new`)
+        let leads_to = |from: GridPosition, to: GridPosition| {
+            DIRECTIONS.iter().any(|direction| {
+                let next = GridPosition::new_from_move(from, *direction, board.size());
+                next == to || through_portals(next, portals) == to
+            })
+        };
+        if let Some(pair) = parts.windows(2).find(|pair| !leads_to(pair[1], pair[0])) {
+            return Err(SnakeError::SaveError(format!(
+                "The snake is broken between ({}, {}) and ({}, {})",
+                pair[0].x, pair[0].y, pair[1].x, pair[1].y
+            )));
+        }
+        Ok(())
+    }
+
+    /// Helper function that returns how many parts of the `Snake` are at the given
+    /// position, or `None` if its occupancy isn't tracked (or the position is off the board).
+    fn occupancy_at(&self, position: GridPosition) -> Option<u8> {
+        if position.x < 0 || position.x >= self.board_width || position.y < 0 {
+            return None;
+        }
+        let index = position.y as usize * self.board_width as usize + position.x as usize;
+        self.occupancy.get(index).copied()
+    }
+
+    /// Helper function that adds (or removes) a part of the `Snake` at the given position
+    /// to its occupancy (which is simply skipped if it isn't tracked).
+    fn change_occupancy(&mut self, position: GridPosition, added: bool) {
+        if position.x < 0 || position.x >= self.board_width || position.y < 0 {
+            return;
+        }
+        let index = position.y as usize * self.board_width as usize + position.x as usize;
+        if let Some(count) = self.occupancy.get_mut(index) {
+            if added {
+                *count += 1;
             } else {
-                self.next_direction = Some(direction);
+                *count -= 1;
             }
+        }
+    }
+
+    /// Sets the direction of the snake.
+    ///
+    /// The direction is queued and applied on one of the following updates,
+    /// which allows quick successive turns (e.g. up then left to round a corner)
+    /// to all register. The direction is validated against the last queued one,
+    /// so a reversal can never be queued.
+    ///
+    /// Reversing the snake returns a `SnakeError::InvalidDirection`, and
+    /// queueing too many turns returns a `SnakeError::LogicError`.
+    pub fn set_direction(&mut self, direction: Direction) -> SnakeResult<()> {
+        let last_direction = self.next_direction();
+        if last_direction.inverse() == direction {
+            Err(SnakeError::InvalidDirection(last_direction))
+        } else if last_direction == direction {
+            // already going there, no need to waste a spot in the queue
             Ok(())
-        } else {
+        } else if self.queued_directions.len() >= MAX_QUEUED_DIRECTIONS {
             Err(SnakeError::LogicError(
-                "Can only update direction if it is orthogonal to previous direction".to_owned(),
+                "Can't queue any more directions until the snake moves".to_owned(),
             ))
+        } else {
+            self.queued_directions.push_back(direction);
+            Ok(())
         }
     }
 
-    /// Returns a `Vec` of ***all*** of the segments of the
-    /// snake (including the head).
+    /// Returns the direction that the snake will be moving in
+    /// once all of the queued turns are applied.
+    pub fn next_direction(&self) -> Direction {
+        *self.queued_directions.back().unwrap_or(&self.direction)
+    }
+
+    /// Returns a `Vec` of the body segments of the snake and its head,
+    /// but ***not*** its tail.
     ///
-    /// This is useful to generate a new position for the
-    /// food and check that the snake isn't already there.
+    /// Kept for the existing callers; use `occupied_cells` to get
+    /// every cell that the snake is on.
     pub fn segments(&self) -> Vec<GridPosition> {
         let mut vec: Vec<GridPosition> = self.body.iter().map(|segment| segment.position).collect();
         vec.push(self.head.position);
         vec
     }
 
+    /// Returns a `Vec` of ***all*** of the cells that the snake
+    /// is on (the head, every body segment and the tail).
+    ///
+    /// This is useful to generate a new position for the
+    /// food and check that the snake isn't already there.
+    pub fn occupied_cells(&self) -> Vec<GridPosition> {
+        let mut vec = self.segments();
+        vec.push(self.tail.position);
+        vec
+    }
+
+    /// Returns the length of the `Snake`, counting
+    /// the head, every body segment and the tail.
+    ///
+    /// This means that a new `Snake` has a length of (at least) `START_LENGTH` (2),
+    /// and that each piece of food eaten adds one to it (see `Snake::grow` for more).
+    // a `Snake` always has a head and a tail, so it can never be empty
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.body.len() + 2
+    }
+
+    /// Makes the `Snake` grow by the given number of segments, on top of the one
+    /// it grows by when eating food: one on each of the following updates.
+    pub fn grow(&mut self, segments: usize) {
+        self.pending_growth += segments;
+    }
+
+    /// Returns the last (up to `TRAIL_LENGTH`) cells that
+    /// the head of the `Snake` was on, newest first.
+    pub fn trail(&self) -> impl Iterator<Item = GridPosition> + '_ {
+        self.trail.iter().copied()
+    }
+
+    /// Shields the `Snake` (or takes the shield away), which should be done before
+    /// every update: taking it away still protects the `Snake` for the next one.
+    pub fn set_shielded(&mut self, shielded: bool) {
+        self.shield_grace = self.shielded && !shielded;
+        self.shielded = shielded;
+    }
+
+    pub fn is_shielded(&self) -> bool {
+        self.shielded
+    }
+
+    /// Helper function that checks whether the `Snake` can't crash in this update.
+    fn is_protected(&self) -> bool {
+        self.shielded || self.shield_grace
+    }
+
+    /// Returns the position of the head of the `Snake`.
+    pub fn head_position(&self) -> GridPosition {
+        self.head.position
+    }
+
+    /// Returns the position of the tail of the `Snake`.
+    pub fn tail_position(&self) -> GridPosition {
+        self.tail.position
+    }
+
     /// Helper function that checks if the `Snake`
-    /// is eating the `Food` in its current state.
-    fn eats_food(&self, food: &Food) -> bool {
-        self.head.position == food.position()
+    /// is eating any of the `Food`s in its current state.
+    ///
+    /// Returns the `Food` that is eaten.
+    fn eats_food<'a>(&self, foods: impl IntoIterator<Item = &'a Food>) -> Option<&'a Food> {
+        foods
+            .into_iter()
+            .find(|food| self.head.position == food.position())
+    }
+
+    /// Helper function that turns the last body segment into the tail,
+    /// which shrinks the `Snake` by one segment.
+    ///
+    /// Returns `false` (leaving the `Snake` untouched) if there is no body left to shrink.
+    fn shrink(&mut self) -> bool {
+        let mut new_tail = match self.body.pop_back() {
+            Some(segment) => segment,
+            None => return false,
+        };
+        let tail_direction = match new_tail.sprite {
+            Sprite::Segment(_, tgt) => tgt,
+            _ => panic!("The body sprite wasn't `Sprite::Segment`."),
+        };
+        new_tail.sprite = Sprite::Tail(tail_direction);
+        self.change_occupancy(self.tail.position, false);
+        self.tail = new_tail;
+        true
     }
 
     /// Helper function that checks if the `Snake`
     /// is eating itself in its current state.
+    ///
+    /// The tail is collidable just like the body. However, this is checked
+    /// ***after*** the tail moves, so the head can safely move into the
+    /// cell that the tail has just left.
+    ///
+    /// A shielded `Snake` never eats itself.
     fn eats_self(&self) -> bool {
-        self.body
-            .iter()
-            .any(|segment| segment.position == self.head.position)
+        if self.is_protected() {
+            return false;
+        }
+        let eats_self = || {
+            self.body
+                .iter()
+                .chain(std::iter::once(&self.tail))
+                .any(|segment| segment.position == self.head.position)
+        };
+        match self.occupancy_at(self.head.position) {
+            // the head is on its cell as well
+            Some(count) => {
+                debug_assert_eq!(count > 1, eats_self(), "The occupancy is out of sync");
+                count > 1
+            }
+            None => eats_self(),
+        }
     }
 
-    /// Updates the state of the `Snake`.
-    pub fn update(&mut self, food: &Food) -> Option<Ate> {
-        // move in the set direction
-        let new_head = PositionedSprite::new(
-            Sprite::Head(self.direction),
-            GridPosition::new_from_move(self.head.position, self.direction),
-        );
+    /// Checks whether any part of the `Snake` (head, body or tail) is at the
+    /// given position, in O(1) (unless its occupancy isn't tracked, see
+    /// `Snake::track_occupancy`).
+    pub fn is_occupied(&self, position: GridPosition) -> bool {
+        match self.occupancy_at(position) {
+            Some(count) => {
+                debug_assert_eq!(
+                    count > 0,
+                    self.occupies(position),
+                    "The occupancy is out of sync"
+                );
+                count > 0
+            }
+            None => self.occupies(position),
+        }
+    }
+
+    /// Checks whether any part of the `Snake` (head, body or tail)
+    /// is at the given position, by going over all of them (see
+    /// `Snake::is_occupied` for the faster check).
+    pub fn occupies(&self, position: GridPosition) -> bool {
+        self.head.position == position
+            || self.tail.position == position
+            || self.body.iter().any(|segment| segment.position == position)
+    }
+
+    /// Updates the state of the `Snake` on the given `Board`,
+    /// where it may eat any of the given `Food`s.
+    ///
+    /// Moving onto either cell of one of the `portals` moves the head
+    /// to the other one instead (keeping its direction).
+    ///
+    /// A shielded `Snake` goes through obstacles, and wraps around
+    /// the edges of the board even in `EdgeBehavior::Wall`.
+    pub fn update<'a>(
+        &mut self,
+        foods: impl IntoIterator<Item = &'a Food>,
+        board: &Board,
+        portals: &[(GridPosition, GridPosition)],
+    ) -> Option<Ate> {
+        // apply the next queued turn (if there is one)
+        let previous_direction = self.direction;
+        if let Some(direction) = self.queued_directions.pop_front() {
+            self.direction = direction;
+        }
+
+        // move in the set direction, unless we crashed into the edge or an obstacle
+        let protected = self.is_protected();
+        let next_position = if protected {
+            GridPosition::try_move(
+                self.head.position,
+                self.direction,
+                board.size(),
+                EdgeBehavior::Wrap,
+            )
+        } else {
+            board.step(self.head.position, self.direction)
+        };
+        let new_position = match next_position.map(|position| through_portals(position, portals)) {
+            Some(position) if protected || !board.is_obstacle(position) => position,
+            _ => return Some(Ate::Wall),
+        };
+        let new_head = PositionedSprite::new(Sprite::Head(self.direction), new_position);
+
+        // remember where the head was for the ghost trail
+        self.trail.truncate(TRAIL_LENGTH - 1);
+        self.trail.push_front(self.head.position);
 
         // push the current head-position to the body
         // as a segment from `previous_direction` -> `direction`
         // and update it to the new one
         self.body.push_front(PositionedSprite::new(
-            Sprite::Segment(self.previous_direction.inverse(), self.direction),
+            Sprite::Segment(previous_direction.inverse(), self.direction),
             self.head.position,
         ));
         self.head = new_head;
+        self.change_occupancy(new_position, true);
 
-        // update the "direction cache"
-        self.previous_direction = self.direction;
-        if let Some(next_direction) = self.next_direction {
-            self.direction = next_direction;
-            self.next_direction = None;
+        // check if the snake is eating (non-poisonous) food, which keeps the tail in
+        // place: a head that ran into the snake itself while eating still collides
+        // (e.g. with food on the tail of a level's start, which the tail doesn't leave)
+        let food = self.eats_food(foods);
+        if let Some(food) = food.filter(|food| food.kind() != FoodKind::Poison) {
+            if self.eats_self() {
+                return Some(Ate::Itself);
+            }
+            return Some(Ate::Food(food.points()));
         }
 
-        // check if the snake is eating something
-        if self.eats_food(food) {
-            Some(Ate::Food)
+        // if the snake didn't eat food, move the last body segment
+        // to the tail to create the illusion of movement
+        // (the head was just pushed to the body, so it can't be empty),
+        // unless it's still growing from earlier food
+        if self.pending_growth > 0 {
+            self.pending_growth -= 1;
         } else {
-            // if the snake didn't eat food, move the last body segment
-            // to the tail to create the illusion of movement
-            let mut new_tail = self.body.pop_back().expect("The body was empty?!");
-            let tail_direction = match new_tail.sprite {
-                Sprite::Segment(_, tgt) => tgt,
-                _ => panic!("The body sprite wasn't `Sprite::Segment`."),
-            };
-            new_tail.sprite = Sprite::Tail(tail_direction);
-            self.tail = new_tail;
+            self.shrink();
+        }
+        let poisoned = match food {
+            // the poison shrinks the snake by another segment
+            Some(_) if self.shrink() => Some(Ate::Poison),
+            Some(_) => return Some(Ate::FatalPoison),
+            None => None,
+        };
 
-            if self.eats_self() {
-                Some(Ate::Itself)
-            } else {
-                None
-            }
+        if self.eats_self() {
+            Some(Ate::Itself)
+        } else {
+            poisoned
         }
     }
 
-    /// Draws the `Snake` to the screen in its current state.
-    pub fn draw(&mut self, ctx: &mut Context, sprites: &mut Image) -> GameResult {
-        // draw the tail
-        let mut param: DrawParam = (&self.tail).into();
-        graphics::draw(ctx, sprites, param)?;
+    /// Draws the ghost trail of the `Snake` to the screen (see `Snake::trail`),
+    /// as `color`ed tiles that fade out the older they are.
+    ///
+    /// This should be drawn before the `Snake` itself, which
+    /// covers the part of the trail that its body is on.
+    pub fn draw_trail(
+        &self,
+        ctx: &mut Context,
+        sprites: &mut Image,
+        cell_size: (i16, i16),
+        color: Color,
+    ) -> GameResult {
+        for (age, position) in self.trail().enumerate() {
+            let fade = 1.0 - age as f32 / TRAIL_LENGTH as f32;
+            let color = Color::new(color.r, color.g, color.b, color.a * 0.5 * fade);
+            let param = PositionedSprite::new(Sprite::Grass, position)
+                .to_draw_param(cell_size)
+                .color(color);
+            graphics::draw(ctx, sprites, param)?;
+        }
+        Ok(())
+    }
+
+    /// Draws the `Snake` to the screen in its current state,
+    /// with tiles of the given pixel size, tinted with `gradient`
+    /// from its head to its tail (`graphics::WHITE` keeps the original
+    /// colors), and with the head scaled by `head_scale` (see `eat_pulse_scale`).
+    ///
+    /// `update_segment` is how far along (between 0.0 and 1.0) the game is
+    /// towards the next update (see `update_segment`): the head leads into
+    /// its next cell and the tail follows it into the body by that much,
+    /// while the rest of the body stays put.
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        sprites: &mut Image,
+        cell_size: (i16, i16),
+        gradient: Gradient,
+        update_segment: f32,
+        head_scale: f32,
+    ) -> GameResult {
+        // the head is at the start of the gradient and the tail at its end
+        let last = self.len().saturating_sub(1).max(1) as f32;
+        let color_at = |index: usize| gradient.at(index as f32 / last);
+
+        // draw the tail, sliding it under the body (which its direction points to),
+        // unless the snake is growing, which leaves the tail in place
+        let tail_direction = match self.tail.sprite {
+            Sprite::Tail(direction) => direction,
+            _ => panic!("The tail sprite wasn't `Sprite::Tail`."),
+        };
+        let tail_segment = if self.pending_growth > 0 {
+            0.0
+        } else {
+            update_segment
+        };
+        graphics::draw(
+            ctx,
+            sprites,
+            self.tail
+                .to_draw_param_towards(cell_size, tail_direction, tail_segment)
+                .color(gradient.tail),
+        )?;
 
         // draw the body
-        for segment in &self.body {
-            param = segment.into();
+        for (index, segment) in self.body.iter().enumerate() {
+            let param = segment.to_draw_param(cell_size).color(color_at(index + 1));
+            graphics::draw(ctx, sprites, param)?;
+        }
+
+        // fill the cell that the head is leaving with the segment that
+        // will replace it, so that the head doesn't detach from the body
+        // (with the turn that's applied next, rather than all of the queued ones)
+        let next_direction = *self.queued_directions.front().unwrap_or(&self.direction);
+        if update_segment > 0.0 {
+            let filler = PositionedSprite::new(
+                Sprite::Segment(self.direction.inverse(), next_direction),
+                self.head.position,
+            );
+            let param = filler.to_draw_param(cell_size).color(color_at(1));
             graphics::draw(ctx, sprites, param)?;
         }
 
         // draw the head last to show it ontop anything else
-        param = (&self.head).into();
-        graphics::draw(ctx, sprites, param)?;
+        let head = PositionedSprite::new(Sprite::Head(next_direction), self.head.position);
+        let param = head.to_draw_param_towards(cell_size, next_direction, update_segment);
+        graphics::draw(
+            ctx,
+            sprites,
+            head.scale_draw_param(param, cell_size, head_scale)
+                .color(gradient.head),
+        )?;
         Ok(())
     }
 }
 
+/// The different kinds of food that the `Snake` can eat.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum FoodKind {
+    /// The regular food, which is always on the board.
+    Normal,
+    /// A golden food that is worth more points, but
+    /// disappears if it isn't eaten in time.
+    Bonus,
+    /// A purple food that shrinks the snake instead of growing it
+    /// (and kills it if it's already as short as it gets).
+    Poison,
+}
+
 /// Represents a piece of food that the `Snake` can eat.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Food {
     segment: PositionedSprite,
+    kind: FoodKind,
+    /// The number of updates left before the food disappears
+    /// (`None` if it never does).
+    lifetime: Option<u32>,
 }
 
 impl Food {
     pub fn new(position: GridPosition) -> Self {
         Self {
             segment: PositionedSprite::new(Sprite::Rabit, position),
+            kind: FoodKind::Normal,
+            lifetime: None,
         }
     }
 
+    /// Creates a new bonus `Food`, which disappears after `BONUS_FOOD_LIFETIME` updates.
+    pub fn new_bonus(position: GridPosition) -> Self {
+        Self {
+            segment: PositionedSprite::new(Sprite::Rabit, position),
+            kind: FoodKind::Bonus,
+            lifetime: Some(BONUS_FOOD_LIFETIME),
+        }
+    }
+
+    /// Creates a new poison `Food`, which disappears after `POISON_FOOD_LIFETIME` updates.
+    pub fn new_poison(position: GridPosition) -> Self {
+        Self {
+            segment: PositionedSprite::new(Sprite::Poison, position),
+            kind: FoodKind::Poison,
+            lifetime: Some(POISON_FOOD_LIFETIME),
+        }
+    }
+
+    pub fn kind(&self) -> FoodKind {
+        self.kind
+    }
+
     pub fn position(&self) -> GridPosition {
         self.segment.position
     }
@@ -165,9 +597,612 @@ impl Food {
         self.segment.position = position;
     }
 
-    pub fn draw(&mut self, ctx: &mut Context, sprites: &mut Image) -> GameResult {
-        let param: DrawParam = (&self.segment).into();
+    /// Returns the number of points that eating this `Food` is worth.
+    pub fn points(&self) -> u32 {
+        match self.kind {
+            FoodKind::Normal => POINTS_PER_FOOD,
+            FoodKind::Bonus => POINTS_PER_FOOD * BONUS_FOOD_MULTIPLIER,
+            FoodKind::Poison => 0,
+        }
+    }
+
+    /// Moves the `Food` by one cell in a random direction on the given `Board`,
+    /// as long as it doesn't move onto an obstacle or any of the `occupied` cells.
+    ///
+    /// The `Food` stays put if it's boxed in.
+    pub fn update<R: Rng>(&mut self, occupied: &[GridPosition], board: &Board, rng: &mut R) {
+        let moves: Vec<GridPosition> = DIRECTIONS
+            .iter()
+            .filter_map(|direction| board.step(self.position(), *direction))
+            .filter(|position| !board.is_obstacle(*position) && !occupied.contains(position))
+            .collect();
+        if let Some(position) = moves.choose(rng) {
+            self.set_position(*position);
+        }
+    }
+
+    /// Moves the `Food` by one cell closer to `target` on the given `Board`,
+    /// as long as it doesn't move onto an obstacle or any of the `occupied` cells.
+    ///
+    /// The `Food` stays put if none of the free cells around it is any closer.
+    pub fn attract(&mut self, target: GridPosition, occupied: &[GridPosition], board: &Board) {
+        let distance = board.distance(self.position(), target);
+        let closest = DIRECTIONS
+            .iter()
+            .filter_map(|direction| board.step(self.position(), *direction))
+            .filter(|position| !board.is_obstacle(*position) && !occupied.contains(position))
+            .min_by_key(|position| board.distance(*position, target));
+        if let Some(position) = closest {
+            if board.distance(position, target) < distance {
+                self.set_position(position);
+            }
+        }
+    }
+
+    /// Advances the lifetime of the `Food` by one update.
+    ///
+    /// Returns whether the `Food` has expired and should be removed.
+    pub fn tick(&mut self) -> bool {
+        match &mut self.lifetime {
+            Some(lifetime) => {
+                *lifetime = lifetime.saturating_sub(1);
+                *lifetime == 0
+            }
+            None => false,
+        }
+    }
+
+    /// Draws the `Food` to the screen, with tiles of the given pixel size.
+    ///
+    /// Normal food is tinted with `color`, while bonus food is always
+    /// golden (and poison food keeps the color of its sprite).
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        sprites: &mut Image,
+        cell_size: (i16, i16),
+        color: Color,
+    ) -> GameResult {
+        let param = self.segment.to_draw_param(cell_size);
+        let param = match self.kind {
+            FoodKind::Normal => param.color(color),
+            FoodKind::Bonus => param.color(Color::from_rgb(255, 215, 0)),
+            FoodKind::Poison => param,
+        };
         graphics::draw(ctx, sprites, param)?;
         Ok(())
     }
 }
+
+/// The power-ups that the `Snake` can pick up.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum PowerUp {
+    /// Pulls the (normal) food towards the head of the snake
+    /// that picked it up, by one cell every update.
+    Magnet,
+    /// Makes the game run `SPEED_BOOST_FACTOR` times faster.
+    Speed,
+    /// Keeps the snake that picked it up from crashing into itself or the walls.
+    Shield,
+}
+
+impl PowerUp {
+    /// All of the power-ups, which are equally likely to spawn.
+    pub const ALL: [PowerUp; 3] = [PowerUp::Magnet, PowerUp::Speed, PowerUp::Shield];
+
+    /// Returns the number of updates that the power-up lasts for once it's picked up.
+    pub fn duration(self) -> u32 {
+        match self {
+            PowerUp::Magnet => MAGNET_DURATION,
+            PowerUp::Speed => SPEED_BOOST_DURATION,
+            PowerUp::Shield => SHIELD_DURATION,
+        }
+    }
+
+    /// Returns the color of the power-up (which its sprite is tinted with).
+    pub fn color(self) -> Color {
+        DrawParam::from(&self.sprite()).color
+    }
+
+    fn sprite(self) -> Sprite {
+        match self {
+            PowerUp::Magnet => Sprite::Magnet,
+            PowerUp::Speed => Sprite::Speed,
+            PowerUp::Shield => Sprite::Shield,
+        }
+    }
+}
+
+/// A power-up lying on the board, which disappears
+/// if it isn't picked up in time (just like bonus food).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PowerUpItem {
+    segment: PositionedSprite,
+    power_up: PowerUp,
+    /// The number of updates left before the power-up disappears.
+    lifetime: u32,
+}
+
+impl PowerUpItem {
+    /// Creates a new `PowerUpItem`, which disappears after `POWER_UP_LIFETIME` updates.
+    pub fn new(power_up: PowerUp, position: GridPosition) -> Self {
+        Self {
+            segment: PositionedSprite::new(power_up.sprite(), position),
+            power_up,
+            lifetime: POWER_UP_LIFETIME,
+        }
+    }
+
+    pub fn power_up(&self) -> PowerUp {
+        self.power_up
+    }
+
+    pub fn position(&self) -> GridPosition {
+        self.segment.position
+    }
+
+    /// Advances the lifetime of the power-up by one update.
+    ///
+    /// Returns whether the power-up has expired and should be removed.
+    pub fn tick(&mut self) -> bool {
+        self.lifetime = self.lifetime.saturating_sub(1);
+        self.lifetime == 0
+    }
+
+    /// Draws the power-up to the screen, with tiles of the given pixel size.
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        sprites: &mut Image,
+        cell_size: (i16, i16),
+    ) -> GameResult {
+        graphics::draw(ctx, sprites, self.segment.to_draw_param(cell_size))?;
+        Ok(())
+    }
+}
+
+/// Helper function that returns where moving onto `position` leads to: the other
+/// cell of the portal it's on (if any), otherwise `position` itself.
+///
+/// Only a single portal is ever taken per move, so landing on a portal
+/// doesn't bounce the snake back and forth between the two of them.
+fn through_portals(
+    position: GridPosition,
+    portals: &[(GridPosition, GridPosition)],
+) -> GridPosition {
+    portals
+        .iter()
+        .find_map(|&(entrance, exit)| {
+            if position == entrance {
+                Some(exit)
+            } else if position == exit {
+                Some(entrance)
+            } else {
+                None
+            }
+        })
+        .unwrap_or(position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Helper function that creates a straight `Snake` of the given length,
+    /// with its head at `(5, 5)` plus `length - START_LENGTH` cells to the right.
+    fn straight_snake(length: usize, board: &Board) -> Snake {
+        let mut snake = Snake::new((5, 5).into(), Direction::Right, START_LENGTH, board);
+        while snake.len() < length {
+            let food = Food::new(GridPosition::new_from_move(
+                snake.head_position(),
+                Direction::Right,
+                board.size(),
+            ));
+            assert!(matches!(
+                snake.update(Some(&food), board, &[]),
+                Some(Ate::Food(POINTS_PER_FOOD))
+            ));
+        }
+        snake
+    }
+
+    /// Helper function that turns the `Snake` in each of the given directions
+    /// (one per update), returning the result of the last update.
+    fn steer(snake: &mut Snake, board: &Board, directions: &[Direction]) -> Option<Ate> {
+        let food = Food::new((0, 0).into());
+        let mut ate = None;
+        for direction in directions {
+            snake.set_direction(*direction).unwrap();
+            ate = snake.update(Some(&food), board, &[]);
+        }
+        ate
+    }
+
+    #[test]
+    fn new_snakes_have_the_given_length() {
+        let board = Board::new(10, 10, EdgeBehavior::Wrap);
+        let snake = Snake::new((1, 0).into(), Direction::Right, 6, &board);
+        assert_eq!(snake.len(), 6);
+        // the body wraps around the left edge
+        for x in &[1, 0, 9, 8, 7, 6] {
+            assert!(snake.occupies((*x, 0).into()));
+        }
+        assert_eq!(
+            Snake::new((1, 0).into(), Direction::Right, 0, &board).len(),
+            START_LENGTH
+        );
+    }
+
+    #[test]
+    fn queued_turns_are_applied_over_successive_updates() {
+        let board = Board::default();
+        let mut snake = Snake::new((5, 5).into(), Direction::Right, START_LENGTH, &board);
+        let food = Food::new((20, 15).into());
+
+        snake.set_direction(Direction::Up).unwrap();
+        snake.set_direction(Direction::Left).unwrap();
+
+        assert!(snake.update(Some(&food), &board, &[]).is_none());
+        assert_eq!(snake.head.position, GridPosition::new(5, 4));
+        assert!(snake.update(Some(&food), &board, &[]).is_none());
+        assert_eq!(snake.head.position, GridPosition::new(4, 4));
+    }
+
+    #[test]
+    fn eating_grows_the_snake() {
+        let board = Board::default();
+        let mut snake = Snake::new((5, 5).into(), Direction::Right, START_LENGTH, &board);
+        let food = Food::new((6, 5).into());
+        assert_eq!(snake.len(), START_LENGTH);
+        assert!(matches!(
+            snake.update(Some(&food), &board, &[]),
+            Some(Ate::Food(POINTS_PER_FOOD))
+        ));
+        assert_eq!(snake.head_position(), food.position());
+        assert_eq!(snake.len(), START_LENGTH + 1);
+    }
+
+    #[test]
+    fn running_into_the_tail_is_a_collision() {
+        // the head turns into the cell of the last body segment,
+        // which becomes the tail on that very same update
+        let board = Board::default();
+        let mut snake = straight_snake(5, &board);
+        let ate = steer(
+            &mut snake,
+            &board,
+            &[Direction::Down, Direction::Left, Direction::Up],
+        );
+        assert!(matches!(ate, Some(Ate::Itself)));
+    }
+
+    #[test]
+    fn following_the_tail_is_not_a_collision() {
+        // the head moves into the cell that the tail is leaving
+        let board = Board::default();
+        let mut snake = straight_snake(4, &board);
+        let ate = steer(
+            &mut snake,
+            &board,
+            &[Direction::Down, Direction::Left, Direction::Up],
+        );
+        assert!(ate.is_none());
+    }
+
+    #[test]
+    fn running_into_the_tail_while_eating_is_a_collision() {
+        // just like `following_the_tail_is_not_a_collision`, but the food on the
+        // tail's cell keeps the tail there, so the collision wins over the food
+        let board = Board::default();
+        let mut snake = straight_snake(4, &board);
+        steer(&mut snake, &board, &[Direction::Down, Direction::Left]);
+        let food = Food::new(snake.tail_position());
+        snake.set_direction(Direction::Up).unwrap();
+        assert_eq!(snake.update(Some(&food), &board, &[]), Some(Ate::Itself));
+
+        // and so does running into the tail while still growing from earlier food
+        let mut snake = straight_snake(4, &board);
+        steer(&mut snake, &board, &[Direction::Down, Direction::Left]);
+        snake.grow(1);
+        assert_eq!(
+            steer(&mut snake, &board, &[Direction::Up]),
+            Some(Ate::Itself)
+        );
+    }
+
+    #[test]
+    fn occupies_every_part_of_the_snake() {
+        let board = Board::default();
+        let snake = straight_snake(4, &board);
+        for x in 4..=7 {
+            assert!(snake.occupies(GridPosition::new(x, 5)));
+        }
+        assert!(!snake.occupies(GridPosition::new(8, 5)));
+        assert!(!snake.occupies(GridPosition::new(5, 6)));
+    }
+
+    #[test]
+    fn occupancy_follows_the_snake() {
+        let board = Board::new(6, 6, EdgeBehavior::Wrap);
+        let mut snake = Snake::new((1, 0).into(), Direction::Right, 4, &board);
+        let food = Food::new((3, 3).into());
+        for _ in 0..20 {
+            let _ = snake.set_direction(Direction::Down);
+            snake.update(Some(&food), &board, &[]);
+            let _ = snake.set_direction(Direction::Right);
+            snake.update(Some(&food), &board, &[]);
+            for cell in board.cells() {
+                assert_eq!(snake.is_occupied(cell), snake.occupies(cell));
+            }
+        }
+        // a loaded snake isn't tracked until it's told to, but still works
+        let mut loaded: Snake =
+            serde_json::from_str(&serde_json::to_string(&snake).unwrap()).unwrap();
+        assert!(loaded.occupancy.is_empty());
+        assert!(loaded.is_occupied(snake.head_position()));
+        loaded.track_occupancy(&board);
+        assert_eq!(loaded.occupancy, snake.occupancy);
+    }
+
+    #[test]
+    fn shielded_snakes_go_through_themselves_and_the_walls() {
+        let board = Board::new(10, 10, EdgeBehavior::Wall);
+        let mut snake = straight_snake(6, &board);
+        snake.set_shielded(true);
+        // curl back into the body
+        let turns = [Direction::Down, Direction::Left, Direction::Up];
+        assert_eq!(steer(&mut snake, &board, &turns), None);
+        // and leave the board on the right, onto its left edge
+        let mut snake = Snake::new((9, 5).into(), Direction::Right, START_LENGTH, &board);
+        snake.set_shielded(true);
+        assert_eq!(snake.update(None, &board, &[]), None);
+        assert_eq!(snake.head_position(), GridPosition::new(0, 5));
+    }
+
+    #[test]
+    fn the_shield_lasts_one_more_update_after_it_wears_off() {
+        let board = Board::new(10, 10, EdgeBehavior::Wall);
+        let mut snake = Snake::new((9, 5).into(), Direction::Right, START_LENGTH, &board);
+        snake.set_shielded(true);
+        let mut unshielded = snake.clone();
+        snake.set_shielded(false);
+        assert!(!snake.is_shielded());
+        assert_eq!(snake.update(None, &board, &[]), None);
+        // but only for one update
+        unshielded.set_shielded(false);
+        unshielded.set_shielded(false);
+        assert_eq!(unshielded.update(None, &board, &[]), Some(Ate::Wall));
+    }
+
+    #[test]
+    fn reset_snakes_start_over() {
+        let board = Board::new(10, 10, EdgeBehavior::Wrap);
+        let mut snake = straight_snake(6, &board);
+        snake.set_direction(Direction::Down).unwrap();
+        snake.reset_to((2, 3).into(), Direction::Left, &board);
+        assert_eq!(snake.len(), START_LENGTH);
+        assert_eq!(snake.head_position(), GridPosition::new(2, 3));
+        assert_eq!(snake.tail_position(), GridPosition::new(3, 3));
+        assert_eq!(snake.next_direction(), Direction::Left);
+        assert!(!snake.is_occupied(GridPosition::new(5, 5)));
+    }
+
+    #[test]
+    fn snake_started_up_has_its_tail_below() {
+        let board = Board::default();
+        let snake = Snake::new((5, 5).into(), Direction::Up, 4, &board);
+        assert_eq!(snake.next_direction(), Direction::Up);
+        assert_eq!(snake.tail.position, GridPosition::new(5, 8));
+        assert_eq!(snake.tail.sprite, Sprite::Tail(Direction::Up));
+        assert!(snake
+            .body
+            .iter()
+            .all(|segment| segment.sprite == Sprite::Segment(Direction::Down, Direction::Up)));
+    }
+
+    #[test]
+    fn occupied_cells_include_the_tail() {
+        let board = Board::default();
+        let snake = straight_snake(4, &board);
+        let cells = snake.occupied_cells();
+        assert_eq!(cells.len(), snake.len());
+        assert!(cells.contains(&snake.tail.position));
+        assert!(!snake.segments().contains(&snake.tail.position));
+    }
+
+    #[test]
+    fn portals_teleport_the_head() {
+        let board = Board::default();
+        let portals = [((7, 5).into(), (20, 10).into())];
+        let mut snake = Snake::new((6, 5).into(), Direction::Right, START_LENGTH, &board);
+        let food = Food::new((0, 0).into());
+        assert!(snake.update(Some(&food), &board, &portals).is_none());
+        assert_eq!(snake.head_position(), GridPosition::new(20, 10));
+        assert_eq!(snake.next_direction(), Direction::Right);
+        // the exit doesn't lead straight back into the entrance
+        assert!(snake.update(Some(&food), &board, &portals).is_none());
+        assert_eq!(snake.head_position(), GridPosition::new(21, 10));
+    }
+
+    #[test]
+    fn trail_keeps_the_latest_head_positions() {
+        let board = Board::default();
+        let mut snake = Snake::new((0, 5).into(), Direction::Right, START_LENGTH, &board);
+        let food = Food::new((20, 15).into());
+        assert_eq!(snake.trail().count(), 0);
+        for _ in 0..TRAIL_LENGTH + 3 {
+            snake.update(Some(&food), &board, &[]);
+        }
+        let trail: Vec<GridPosition> = snake.trail().collect();
+        assert_eq!(trail.len(), TRAIL_LENGTH);
+        assert_eq!(trail[0], GridPosition::new(TRAIL_LENGTH as i16 + 2, 5));
+        assert_eq!(trail[TRAIL_LENGTH - 1], GridPosition::new(3, 5));
+    }
+
+    #[test]
+    fn leaving_a_walled_board_hits_the_wall() {
+        let board = Board::new(10, 10, EdgeBehavior::Wall);
+        let mut snake = Snake::new((9, 5).into(), Direction::Right, START_LENGTH, &board);
+        let food = Food::new((0, 0).into());
+        assert!(matches!(
+            snake.update(Some(&food), &board, &[]),
+            Some(Ate::Wall)
+        ));
+    }
+
+    #[test]
+    fn moving_food_only_moves_onto_free_cells() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let board = Board::new(10, 10, EdgeBehavior::Wall);
+        // the food is in the corner, with the cell to its right taken
+        let occupied = [GridPosition::new(1, 0)];
+        for _ in 0..10 {
+            let mut food = Food::new((0, 0).into());
+            food.update(&occupied, &board, &mut rng);
+            assert_eq!(food.position(), GridPosition::new(0, 1));
+        }
+        // and once it's boxed in, it stays put
+        let mut food = Food::new((0, 0).into());
+        food.update(&[(1, 0).into(), (0, 1).into()], &board, &mut rng);
+        assert_eq!(food.position(), GridPosition::new(0, 0));
+    }
+
+    #[test]
+    fn attracted_food_moves_closer_without_touching_the_snake() {
+        let board = Board::new(10, 10, EdgeBehavior::Wall);
+        let snake = straight_snake(4, &board);
+        let head = snake.head_position();
+        let mut food = Food::new(GridPosition::new(head.x + 3, head.y));
+        let occupied = snake.occupied_cells();
+        for _ in 0..5 {
+            food.attract(head, &occupied, &board);
+            assert!(!snake.occupies(food.position()));
+        }
+        // it stops right next to the head, and never lands on it
+        assert_eq!(food.position(), GridPosition::new(head.x + 1, head.y));
+    }
+
+    #[test]
+    fn power_ups_expire_after_their_lifetime() {
+        let mut item = PowerUpItem::new(PowerUp::Magnet, GridPosition::new(1, 1));
+        for _ in 1..POWER_UP_LIFETIME {
+            assert!(!item.tick());
+        }
+        assert!(item.tick());
+    }
+
+    #[test]
+    fn eating_bonus_food_is_worth_more_points() {
+        let board = Board::default();
+        let mut snake = Snake::new((5, 5).into(), Direction::Right, START_LENGTH, &board);
+        let food = Food::new((20, 15).into());
+        let bonus_food = Food::new_bonus((6, 5).into());
+        assert_eq!(
+            snake.update(vec![&food, &bonus_food], &board, &[]),
+            Some(Ate::Food(POINTS_PER_FOOD * BONUS_FOOD_MULTIPLIER))
+        );
+    }
+
+    #[test]
+    fn growing_keeps_the_tail_in_place() {
+        let board = Board::default();
+        let mut snake = Snake::new((5, 5).into(), Direction::Right, START_LENGTH, &board);
+        let food = Food::new((20, 15).into());
+        snake.grow(2);
+        for length in &[3, 4, 4] {
+            assert!(snake.update(Some(&food), &board, &[]).is_none());
+            assert_eq!(snake.len(), *length);
+            assert_eq!(snake.tail.sprite, Sprite::Tail(Direction::Right));
+        }
+        assert_eq!(snake.tail.position, GridPosition::new(5, 5));
+    }
+
+    #[test]
+    fn poison_shrinks_the_snake() {
+        let board = Board::default();
+        let mut snake = straight_snake(4, &board);
+        let poison = Food::new_poison(GridPosition::new_from_move(
+            snake.head_position(),
+            Direction::Right,
+            board.size(),
+        ));
+        assert_eq!(snake.update(Some(&poison), &board, &[]), Some(Ate::Poison));
+        assert_eq!(snake.len(), 3);
+        assert_eq!(snake.occupied_cells().len(), 3);
+        assert!(matches!(snake.tail.sprite, Sprite::Tail(Direction::Right)));
+    }
+
+    #[test]
+    fn poison_at_the_minimum_length_is_fatal() {
+        let board = Board::default();
+        let mut snake = straight_snake(START_LENGTH + 1, &board);
+        let next_poison = |snake: &Snake| {
+            Food::new_poison(GridPosition::new_from_move(
+                snake.head_position(),
+                Direction::Right,
+                board.size(),
+            ))
+        };
+        let poison = next_poison(&snake);
+        assert_eq!(snake.update(Some(&poison), &board, &[]), Some(Ate::Poison));
+        assert_eq!(snake.len(), START_LENGTH);
+        let poison = next_poison(&snake);
+        assert_eq!(
+            snake.update(Some(&poison), &board, &[]),
+            Some(Ate::FatalPoison)
+        );
+        assert_eq!(snake.len(), START_LENGTH);
+    }
+
+    #[test]
+    fn bonus_food_expires_after_its_lifetime() {
+        let mut food = Food::new_bonus((0, 0).into());
+        for _ in 1..BONUS_FOOD_LIFETIME {
+            assert!(!food.tick());
+        }
+        assert!(food.tick());
+        assert!(!Food::new((0, 0).into()).tick());
+    }
+
+    #[test]
+    fn moving_into_an_obstacle_hits_the_wall() {
+        let mut board = Board::default();
+        board.obstacles.push(GridPosition::new(6, 5));
+        let mut snake = Snake::new((5, 5).into(), Direction::Right, START_LENGTH, &board);
+        let food = Food::new((0, 0).into());
+        assert!(matches!(
+            snake.update(Some(&food), &board, &[]),
+            Some(Ate::Wall)
+        ));
+    }
+
+    #[test]
+    fn reversal_of_a_queued_turn_is_rejected() {
+        let mut snake = Snake::new(
+            (5, 5).into(),
+            Direction::Right,
+            START_LENGTH,
+            &Board::default(),
+        );
+        snake.set_direction(Direction::Up).unwrap();
+        assert!(matches!(
+            snake.set_direction(Direction::Down),
+            Err(SnakeError::InvalidDirection(Direction::Up))
+        ));
+    }
+
+    #[test]
+    fn queue_is_bounded() {
+        let mut snake = Snake::new(
+            (5, 5).into(),
+            Direction::Right,
+            START_LENGTH,
+            &Board::default(),
+        );
+        snake.set_direction(Direction::Up).unwrap();
+        snake.set_direction(Direction::Left).unwrap();
+        assert!(snake.set_direction(Direction::Down).is_err());
+    }
+}
@@ -0,0 +1,1722 @@
+use crate::{ai::*, board::*, config::*, entities::*, primitives::*, replay::*, save::*};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The value of an empty cell (or a portal) in `Game::board_tensor`.
+pub const CELL_EMPTY: u8 = 0;
+/// The value of a body segment of a snake in `Game::board_tensor`.
+pub const CELL_BODY: u8 = 1;
+/// The value of the head of a snake in `Game::board_tensor`.
+pub const CELL_HEAD: u8 = 2;
+/// The value of the tail of a snake in `Game::board_tensor`.
+pub const CELL_TAIL: u8 = 3;
+/// The value of any of the foods in `Game::board_tensor`.
+pub const CELL_FOOD: u8 = 4;
+/// The value of an obstacle in `Game::board_tensor`.
+pub const CELL_WALL: u8 = 5;
+
+/// The result of advancing the `Game` by a single tick.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StepResult {
+    /// The snake moved without eating anything.
+    Moved,
+    /// The snake ate a piece of food, which was worth the given points.
+    Ate(u32),
+    /// The snake died, either by eating itself or by crashing into a wall.
+    Died,
+    /// The snake filled the whole board (or, in a two-player
+    /// game, the snakes filled it together).
+    Won,
+    /// The given player lost a two-player game, by running into itself,
+    /// a wall or the other player's snake.
+    PlayerLost(Player),
+    /// Both players of a two-player game died at the same time
+    /// (e.g. when their heads collided).
+    Draw,
+    /// The given player's snake died in a `GameMode::Practice` game,
+    /// so it started over (and the game goes on).
+    Respawned(Player),
+    /// The time limit of a `GameMode::TimeAttack` game ran out.
+    TimeUp,
+}
+
+/// The players of a two-player game.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Player {
+    /// Player one, who also plays the single-player game.
+    One,
+    Two,
+}
+
+impl Player {
+    /// Both of the players, in order.
+    pub const ALL: [Player; 2] = [Player::One, Player::Two];
+
+    /// Returns the index of the player's snake in `Game::snakes`.
+    pub fn index(self) -> usize {
+        match self {
+            Player::One => 0,
+            Player::Two => 1,
+        }
+    }
+}
+
+/// Gets notified of what happens in a `Game` as it happens (e.g. to integrate
+/// it into something else), once it's set with `Game::set_observer`.
+///
+/// Every method does nothing by default, so an observer only
+/// has to implement the events that it's interested in.
+pub trait GameObserver {
+    /// The given player's snake ate a food, which was worth the given points.
+    fn on_eat(&mut self, _player: Player, _points: u32) {}
+
+    /// The given player's snake died (which a `GameMode::Practice` game survives).
+    fn on_death(&mut self, _player: Player) {}
+
+    /// The given player turned their snake (the autopilot's turns don't count).
+    fn on_turn(&mut self, _player: Player, _direction: Direction) {}
+}
+
+/// A power-up that a snake has picked up, and is still in effect.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ActivePowerUp {
+    pub power_up: PowerUp,
+    /// The player whose snake picked up the power-up.
+    pub player: Player,
+    /// The number of updates left before the power-up wears off.
+    pub ticks_left: u32,
+}
+
+impl ActivePowerUp {
+    /// Returns how much of the power-up is left, from 1 (just picked up) to 0.
+    pub fn fraction_left(&self) -> f32 {
+        self.ticks_left as f32 / self.power_up.duration() as f32
+    }
+}
+
+/// The core simulation of the game: the board, the snake (or the snakes,
+/// in a two-player game), the food they share and the scores.
+///
+/// It knows nothing about rendering, audio or timing (and doesn't need
+/// a ggez `Context`), so it can also be run headless, e.g. for benchmarks
+/// and AI experiments (see `examples/headless.rs`).
+pub struct Game {
+    board: Board,
+    rng: StdRng,
+    /// The snake of each player, in the order of `Player`.
+    snakes: Vec<Snake>,
+    /// The normal foods (there's always at least one, see `GameConfig::food_count`).
+    foods: Vec<Food>,
+    /// The bonus food, which only spawns every now and then.
+    bonus_food: Option<Food>,
+    /// The poison food, which only spawns every now and then as well.
+    poison_food: Option<Food>,
+    /// The linked pairs of portals: moving onto either cell
+    /// of a pair moves the snake to the other one.
+    portals: Vec<(GridPosition, GridPosition)>,
+    /// Whether the food moves every `FOOD_MOVE_INTERVAL` ticks.
+    moving_food: bool,
+    /// Whether food avoids spawning next to the heads of the snakes (see `Game::safe_zone`).
+    safe_spawn: bool,
+    /// Whether power-ups spawn every now and then (see `POWER_UP_CHANCE`).
+    power_ups: bool,
+    /// The power-up waiting on the board to be picked up (if any).
+    power_up_item: Option<PowerUpItem>,
+    /// The power-up that's currently in effect (if any).
+    active_power_up: Option<ActivePowerUp>,
+    /// The score of each player, in the order of `Player`.
+    scores: Vec<u32>,
+    /// The number of foods that each player ate in a row, each within
+    /// `COMBO_WINDOW` ticks of the last one (see `combo_multiplier`).
+    combos: Vec<u32>,
+    /// The number of ticks since each player last ate a food.
+    ticks_since_food: Vec<u32>,
+    /// The number of times that each player died (in `GameMode::Practice`).
+    deaths: Vec<u32>,
+    /// The autopilot that steers player one's snake (if enabled).
+    ai: Option<AiController>,
+    /// The number of ticks that the game was stepped so far.
+    tick: u64,
+    mode: GameMode,
+    /// The direction that the snakes start moving in (and start over in, see `Game::respawn`).
+    start_direction: Direction,
+    /// Where player one's snake starts on a custom level (see `Level::start`).
+    level_start: Option<GridPosition>,
+    difficulty: Difficulty,
+    /// How the game speeds up as the snakes grow.
+    speed_curve: SpeedCurve,
+    /// The number of segments that a snake grows by for each piece of food.
+    growth: usize,
+    /// The most ticks that a snake can go without eating before it starves (if any).
+    starvation: Option<u32>,
+    /// How long the game was played for, which is the sum of the
+    /// intervals of all of the ticks so far (so it's deterministic,
+    /// and doesn't include any time spent paused).
+    elapsed: Duration,
+    /// The recording of the run so far (see `Game::recording`).
+    recording: Option<Recording>,
+    /// Whether the ticks last `SLOW_MOTION_FACTOR` times longer (for debugging).
+    slow_motion: bool,
+    /// Whether a single tick was requested while paused (see `Game::request_step`).
+    step_requested: bool,
+    /// Gets notified of the events of the game (if set, see `Game::set_observer`).
+    observer: Option<Box<dyn GameObserver>>,
+}
+
+/// Helper function that returns where the head of the given player's snake starts,
+/// when moving in `direction`: one cell away from the edge behind it, with
+/// player one in the first row (or column) and player two in the last one.
+fn start_position(board: &Board, direction: Direction, player: Player) -> GridPosition {
+    let (width, height) = board.size();
+    let lane = |size: i16| match player {
+        Player::One => 0,
+        Player::Two => size - 1,
+    };
+    match direction {
+        Direction::Right => GridPosition::new(1, lane(height)),
+        Direction::Left => GridPosition::new(width - 2, lane(height)),
+        Direction::Down => GridPosition::new(lane(width), 1),
+        Direction::Up => GridPosition::new(lane(width), height - 2),
+    }
+}
+
+/// Builds a `Game` step by step, starting from the default configuration
+/// (so whatever isn't set behaves just like it always has).
+#[derive(Clone, Debug, Default)]
+pub struct GameBuilder {
+    config: GameConfig,
+}
+
+impl GameBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts from the given configuration instead of the default one.
+    pub fn from_config(config: GameConfig) -> Self {
+        Self { config }
+    }
+
+    /// Sets the size of the board (in grid cells).
+    pub fn size(mut self, width: i16, height: i16) -> Self {
+        self.config.width = width;
+        self.config.height = height;
+        self
+    }
+
+    pub fn difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.config.difficulty = difficulty;
+        self
+    }
+
+    /// Seeds the random number generator, which makes the game deterministic.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    pub fn edges(mut self, edges: EdgeBehavior) -> Self {
+        self.config.edges = edges;
+        self
+    }
+
+    pub fn start_length(mut self, length: usize) -> Self {
+        self.config.start_length = length;
+        self
+    }
+
+    pub fn start_direction(mut self, direction: Direction) -> Self {
+        self.config.start_direction = direction;
+        self
+    }
+
+    pub fn mode(mut self, mode: GameMode) -> Self {
+        self.config.mode = mode;
+        self
+    }
+
+    pub fn two_players(mut self, two_players: bool) -> Self {
+        self.config.two_players = two_players;
+        self
+    }
+
+    /// Sets the number of (normal) foods on the board at once.
+    pub fn food_count(mut self, count: usize) -> Self {
+        self.config.food_count = count;
+        self
+    }
+
+    /// The configuration that's been built so far (e.g. for the rendering
+    /// settings, which the `Game` itself doesn't keep).
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    /// Creates the `Game`, or returns a `SnakeError::ArgumentError`
+    /// if the configuration isn't playable (see `GameConfig::validate`).
+    pub fn build(&self) -> SnakeResult<Game> {
+        self.config.validate()?;
+        Ok(Game::new(&self.config))
+    }
+}
+
+impl Game {
+    /// Returns a `GameBuilder`, starting from the default configuration.
+    pub fn builder() -> GameBuilder {
+        GameBuilder::new()
+    }
+
+    /// Creates a new `Game` in its starting state, as described by the configuration.
+    pub fn new(config: &GameConfig) -> Self {
+        let board = config.board();
+        // an unseeded game still picks a seed, so that it can be replayed
+        let seed = config.seed.unwrap_or_else(rand::random);
+        let length = config.start_length;
+        let direction = config.start_direction;
+        let level_start = config.level.as_ref().map(|level| level.start);
+        let start = |player| {
+            let position = match (player, level_start) {
+                (Player::One, Some(position)) => position,
+                _ => start_position(&board, direction, player),
+            };
+            Snake::new(position, direction, length, &board)
+        };
+        let mut snakes = vec![start(Player::One)];
+        if config.two_players {
+            // player two starts on the other side of the board
+            snakes.push(start(Player::Two));
+        }
+        let mut game = Self {
+            foods: vec![Food::new((board.width / 2, board.height / 2).into())],
+            bonus_food: None,
+            poison_food: None,
+            portals: if config.portals {
+                vec![board.default_portals()]
+            } else {
+                Vec::new()
+            },
+            moving_food: config.moving_food,
+            safe_spawn: config.safe_spawn,
+            power_ups: config.power_ups,
+            power_up_item: None,
+            active_power_up: None,
+            scores: vec![0; snakes.len()],
+            combos: vec![0; snakes.len()],
+            ticks_since_food: vec![0; snakes.len()],
+            deaths: vec![0; snakes.len()],
+            snakes,
+            ai: if config.ai { Some(AiController) } else { None },
+            rng: StdRng::seed_from_u64(seed),
+            tick: 0,
+            mode: config.mode,
+            start_direction: config.start_direction,
+            level_start,
+            difficulty: config.difficulty,
+            speed_curve: config.speed_curve,
+            growth: config.growth,
+            starvation: config.starvation,
+            elapsed: Duration::default(),
+            recording: Some(Recording::new(seed)),
+            slow_motion: false,
+            step_requested: false,
+            observer: None,
+            board,
+        };
+        // a level may place the food itself, or have a wall (or the snake) in the center
+        if let Some(level) = &config.level {
+            let position = level.food.or_else(|| {
+                let center = game.foods[0].position();
+                let blocked = game.board.is_obstacle(center) || game.snakes[0].occupies(center);
+                if blocked {
+                    game.generate_food_position()
+                } else {
+                    Some(center)
+                }
+            });
+            if let Some(position) = position {
+                game.foods[0].set_position(position);
+            }
+        }
+        // the rest of the foods (if there are more) spawn anywhere, just like they respawn
+        for _ in 1..config.food_count {
+            if let Some(position) = game.generate_food_position() {
+                game.foods.push(Food::new(position));
+            }
+        }
+        game
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Sets the observer that gets notified of the events of the game
+    /// from now on (replacing any previous one).
+    pub fn set_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.observer = Some(observer);
+    }
+
+    /// Removes the observer of the game (if any), and returns it.
+    pub fn take_observer(&mut self) -> Option<Box<dyn GameObserver>> {
+        self.observer.take()
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    /// Changes how fast the game is from now on (e.g. from the settings menu).
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+    }
+
+    /// Returns the number of ticks that the game was stepped so far.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// Returns how long each tick currently lasts (which gets shorter
+    /// as the longest snake grows, and longer in slow motion).
+    pub fn update_interval(&self) -> Duration {
+        if self.slow_motion {
+            self.tick_length() * SLOW_MOTION_FACTOR
+        } else {
+            self.tick_length()
+        }
+    }
+
+    /// Helper function that returns how much game time each tick takes, which
+    /// (unlike `update_interval`) isn't affected by slow motion.
+    ///
+    /// The speed boost power-up makes the ticks `SPEED_BOOST_FACTOR` times
+    /// shorter than they are for the length of the longest snake.
+    fn tick_length(&self) -> Duration {
+        let length = self
+            .snakes
+            .iter()
+            .map(Snake::len)
+            .max()
+            .unwrap_or(START_LENGTH);
+        let tick =
+            Duration::from_millis(self.speed_curve.millis_per_update(length, self.difficulty));
+        if self.has_power_up(PowerUp::Speed) {
+            tick.div_f64(SPEED_BOOST_FACTOR)
+        } else {
+            tick
+        }
+    }
+
+    /// Slows the ticks down (or brings them back to speed), for debugging.
+    pub fn set_slow_motion(&mut self, slow_motion: bool) {
+        self.slow_motion = slow_motion;
+    }
+
+    pub fn is_slow_motion(&self) -> bool {
+        self.slow_motion
+    }
+
+    /// Requests a single tick to be run while the game is paused (for debugging),
+    /// which whoever runs the game picks up with `take_step_request`.
+    pub fn request_step(&mut self) {
+        self.step_requested = true;
+    }
+
+    /// Returns whether a single tick was requested, clearing the request.
+    pub fn take_step_request(&mut self) -> bool {
+        std::mem::take(&mut self.step_requested)
+    }
+
+    /// Returns how much time is left until a `GameMode::TimeAttack`
+    /// game ends, or `None` if the game has no time limit.
+    pub fn time_left(&self) -> Option<Duration> {
+        match self.mode {
+            GameMode::Endless | GameMode::Practice => None,
+            GameMode::TimeAttack { limit } => Some(limit.saturating_sub(self.elapsed)),
+        }
+    }
+
+    /// Returns the recording of the run so far, which can replay it,
+    /// or `None` if the run was restored from a save (as the recording
+    /// can only reproduce a run from its start).
+    pub fn recording(&self) -> Option<&Recording> {
+        self.recording.as_ref()
+    }
+
+    /// Returns player one's snake.
+    pub fn snake(&self) -> &Snake {
+        &self.snakes[0]
+    }
+
+    /// Returns the snakes of all of the players, in the order of `Player`.
+    pub fn snakes(&self) -> &[Snake] {
+        &self.snakes
+    }
+
+    /// Checks whether this is a two-player game.
+    pub fn is_two_players(&self) -> bool {
+        self.snakes.len() > 1
+    }
+
+    /// Returns the first of the normal foods (which is the only
+    /// one, unless more were asked for, see `Game::foods`).
+    pub fn food(&self) -> &Food {
+        &self.foods[0]
+    }
+
+    /// Returns all of the normal foods.
+    pub fn foods(&self) -> &[Food] {
+        &self.foods
+    }
+
+    pub fn bonus_food(&self) -> Option<&Food> {
+        self.bonus_food.as_ref()
+    }
+
+    pub fn power_up_item(&self) -> Option<&PowerUpItem> {
+        self.power_up_item.as_ref()
+    }
+
+    pub fn active_power_up(&self) -> Option<ActivePowerUp> {
+        self.active_power_up
+    }
+
+    /// Returns the fraction (from 1 down to 0) of the ticks that the given player's
+    /// snake has left to eat before it starves, or `None` if snakes don't starve.
+    pub fn hunger_left(&self, player: Player) -> Option<f32> {
+        let limit = self.starvation?;
+        let ticks = *self.ticks_since_food.get(player.index())?;
+        Some(1.0 - ticks.min(limit) as f32 / limit as f32)
+    }
+
+    /// Checks whether the given power-up is currently in effect.
+    pub fn has_power_up(&self, power_up: PowerUp) -> bool {
+        self.active_power_up
+            .is_some_and(|active| active.power_up == power_up)
+    }
+
+    pub fn portals(&self) -> &[(GridPosition, GridPosition)] {
+        &self.portals
+    }
+
+    pub fn poison_food(&self) -> Option<&Food> {
+        self.poison_food.as_ref()
+    }
+
+    /// Returns player one's score.
+    pub fn score(&self) -> u32 {
+        self.scores[0]
+    }
+
+    /// Returns the scores of all of the players, in the order of `Player`.
+    pub fn scores(&self) -> &[u32] {
+        &self.scores
+    }
+
+    /// Returns the number of times that each player died, in the order of
+    /// `Player` (which only a `GameMode::Practice` game survives).
+    pub fn deaths(&self) -> &[u32] {
+        &self.deaths
+    }
+
+    /// Returns the current combo of each player, in the order of `Player`.
+    pub fn combos(&self) -> &[u32] {
+        &self.combos
+    }
+
+    /// Steers player one's snake in the given direction (see `Snake::set_direction`).
+    ///
+    /// This is also how a program drives the game (e.g. with `Game::step_logic`): the
+    /// turn is added after the turns that are already buffered (up to
+    /// `MAX_QUEUED_DIRECTIONS` of them), instead of replacing them, and each step applies
+    /// exactly one of them. So a driver that steers once before every step always
+    /// has its turn applied on that very step.
+    pub fn set_direction(&mut self, direction: Direction) -> SnakeResult {
+        self.set_player_direction(Player::One, direction)
+    }
+
+    /// Steers the given player's snake in the given direction.
+    ///
+    /// Steering a player that isn't in the game is a `SnakeError::LogicError`.
+    pub fn set_player_direction(&mut self, player: Player, direction: Direction) -> SnakeResult {
+        let snake = match self.snakes.get_mut(player.index()) {
+            Some(snake) => snake,
+            None => {
+                return Err(SnakeError::LogicError(format!(
+                    "{:?} isn't playing",
+                    player
+                )))
+            }
+        };
+        let previous_direction = snake.next_direction();
+        snake.set_direction(direction)?;
+        // only the turns that changed anything are needed to replay the run
+        if snake.next_direction() == previous_direction {
+            return Ok(());
+        }
+        if let Some(recording) = &mut self.recording {
+            recording.inputs.push(Input {
+                tick: self.tick,
+                player,
+                direction,
+            });
+        }
+        if let Some(observer) = &mut self.observer {
+            observer.on_turn(player, direction);
+        }
+        Ok(())
+    }
+
+    /// Advances the game by a single tick.
+    ///
+    /// Once the game has ended (any snake died or the board is full),
+    /// it shouldn't be stepped any further.
+    pub fn step_logic(&mut self) -> StepResult {
+        self.tick += 1;
+        self.elapsed += self.tick_length();
+
+        // let the autopilot steer first (it plays just like the player would)
+        if let Some(direction) = self.ai.and_then(|ai| ai.choose(self)) {
+            let _ = self.snakes[0].set_direction(direction);
+        }
+
+        // the bonus food disappears if it isn't eaten in time
+        if self.bonus_food.as_mut().is_some_and(Food::tick) {
+            self.bonus_food = None;
+        }
+        if self.poison_food.as_mut().is_some_and(Food::tick) {
+            self.poison_food = None;
+        }
+        if self.power_up_item.as_mut().is_some_and(PowerUpItem::tick) {
+            self.power_up_item = None;
+        }
+
+        if let Some(active) = self
+            .active_power_up
+            .filter(|active| active.power_up == PowerUp::Magnet)
+        {
+            // the magnet pulls the (normal) foods towards the head, instead of letting them wander
+            let head = self.snakes[active.player.index()].head_position();
+            for index in 0..self.foods.len() {
+                let occupied = self.occupied_cells();
+                self.foods[index].attract(head, &occupied, &self.board);
+            }
+        } else if self.moving_food && self.tick.is_multiple_of(FOOD_MOVE_INTERVAL) {
+            // every now and then, the (normal) foods wander off
+            for index in 0..self.foods.len() {
+                let occupied = self.occupied_cells();
+                self.foods[index].update(&occupied, &self.board, &mut self.rng);
+            }
+        }
+
+        // the power-up in effect wears off in time
+        self.active_power_up = self
+            .active_power_up
+            .map(|active| ActivePowerUp {
+                ticks_left: active.ticks_left - 1,
+                ..active
+            })
+            .filter(|active| active.ticks_left > 0);
+
+        // the shield protects the snake of the player who picked it up
+        let shielded = self
+            .active_power_up
+            .filter(|active| active.power_up == PowerUp::Shield)
+            .map(|active| active.player);
+        for (player, snake) in Player::ALL.iter().copied().zip(&mut self.snakes) {
+            snake.set_shielded(shielded == Some(player));
+        }
+
+        // the combos run out if no food is eaten in time
+        for (combo, ticks) in self.combos.iter_mut().zip(&mut self.ticks_since_food) {
+            *ticks += 1;
+            if *ticks > COMBO_WINDOW {
+                *combo = 0;
+            }
+        }
+
+        // move all of the snakes before judging any of them,
+        // so that neither player gets an advantage
+        let mut ate = Vec::with_capacity(self.snakes.len());
+        for snake in &mut self.snakes {
+            let foods = self
+                .foods
+                .iter()
+                .chain(&self.bonus_food)
+                .chain(&self.poison_food);
+            ate.push(snake.update(foods, &self.board, &self.portals));
+        }
+
+        // a snake dies if it ate itself, crashed into a wall or ran
+        // into another snake (heads colliding kills both snakes)
+        let mut died: Vec<bool> = (0..self.snakes.len())
+            .map(|index| {
+                let head = self.snakes[index].head_position();
+                matches!(
+                    ate[index],
+                    Some(Ate::Itself) | Some(Ate::Wall) | Some(Ate::FatalPoison)
+                ) || self
+                    .snakes
+                    .iter()
+                    .enumerate()
+                    .any(|(other, snake)| other != index && snake.is_occupied(head))
+            })
+            .collect();
+
+        // the snakes that survived still get the food they ate
+        // (and the poison they ate is gone, too)
+        let mut result = StepResult::Moved;
+        for (index, ate) in ate.into_iter().enumerate() {
+            match (ate, died[index]) {
+                (Some(Ate::Food(points)), false) => {
+                    self.combos[index] += 1;
+                    self.ticks_since_food[index] = 0;
+                    let points = points * combo_multiplier(self.combos[index]);
+                    result = self.eat_food(index, points);
+                    if let Some(observer) = &mut self.observer {
+                        observer.on_eat(Player::ALL[index], points);
+                    }
+                }
+                (Some(Ate::Poison), false) => self.poison_food = None,
+                _ => {}
+            }
+        }
+        // and a snake that went too long without eating starves
+        if let Some(limit) = self.starvation {
+            for (died, ticks) in died.iter_mut().zip(&self.ticks_since_food) {
+                *died |= *ticks > limit;
+            }
+        }
+        if let Some(observer) = &mut self.observer {
+            for (player, _) in Player::ALL.iter().zip(&died).filter(|(_, died)| **died) {
+                observer.on_death(*player);
+            }
+        }
+
+        // a snake that survived picks up the power-up that its head moved onto
+        let picked_up = self.power_up_item.as_ref().and_then(|item| {
+            Player::ALL
+                .iter()
+                .copied()
+                .take(self.snakes.len())
+                .find(|player| {
+                    !died[player.index()]
+                        && self.snakes[player.index()].head_position() == item.position()
+                })
+                .map(|player| (player, item.power_up()))
+        });
+        if let Some((player, power_up)) = picked_up {
+            self.active_power_up = Some(ActivePowerUp {
+                power_up,
+                player,
+                ticks_left: power_up.duration(),
+            });
+            self.power_up_item = None;
+        }
+
+        // in practice, the snakes that died start over instead (while there's room)
+        if self.mode == GameMode::Practice && result != StepResult::Won {
+            let mut respawned = None;
+            for player in Player::ALL.iter().copied().take(self.snakes.len()) {
+                if died[player.index()] && self.respawn(player) {
+                    died[player.index()] = false;
+                    respawned = Some(player);
+                }
+            }
+            if let (Some(player), false) = (respawned, died.contains(&true)) {
+                return StepResult::Respawned(player);
+            }
+        }
+
+        match died.as_slice() {
+            [true] => StepResult::Died,
+            [true, true] => StepResult::Draw,
+            [true, false] => StepResult::PlayerLost(Player::One),
+            [false, true] => StepResult::PlayerLost(Player::Two),
+            // filling the board still wins, even on the last tick
+            _ if result != StepResult::Won && self.time_left() == Some(Duration::default()) => {
+                StepResult::TimeUp
+            }
+            _ => result,
+        }
+    }
+
+    /// Renders the board as ASCII, one line per row, for debugging (e.g. in tests):
+    /// `H` is a head, `o` a body segment, `t` a tail, `*` any of the foods,
+    /// `#` an obstacle, `@` a portal and `.` an empty cell.
+    pub fn debug_board(&self) -> String {
+        let (width, height) = self.board.size();
+        let mut rows = vec![vec!['.'; width as usize]; height as usize];
+        let mut mark = |position: GridPosition, c: char| {
+            rows[position.y as usize][position.x as usize] = c;
+        };
+        for obstacle in &self.board.obstacles {
+            mark(*obstacle, '#');
+        }
+        for &(entrance, exit) in &self.portals {
+            mark(entrance, '@');
+            mark(exit, '@');
+        }
+        let foods = self
+            .foods
+            .iter()
+            .chain(&self.bonus_food)
+            .chain(&self.poison_food);
+        for food in foods {
+            mark(food.position(), '*');
+        }
+        for snake in &self.snakes {
+            for segment in snake.segments() {
+                mark(segment, 'o');
+            }
+            mark(snake.tail_position(), 't');
+            mark(snake.head_position(), 'H');
+        }
+        rows.into_iter()
+            .map(|row| row.into_iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Returns a numeric view of the board (e.g. for training an agent), indexed by
+    /// `[y][x]`: every cell is one of `CELL_EMPTY`, `CELL_BODY`, `CELL_HEAD`,
+    /// `CELL_TAIL`, `CELL_FOOD` or `CELL_WALL` (like `Game::debug_board`).
+    pub fn board_tensor(&self) -> Vec<Vec<u8>> {
+        let (width, height) = self.board.size();
+        let mut rows = vec![vec![CELL_EMPTY; width as usize]; height as usize];
+        let mut mark = |position: GridPosition, value: u8| {
+            rows[position.y as usize][position.x as usize] = value;
+        };
+        for obstacle in &self.board.obstacles {
+            mark(*obstacle, CELL_WALL);
+        }
+        let foods = self
+            .foods
+            .iter()
+            .chain(&self.bonus_food)
+            .chain(&self.poison_food);
+        for food in foods {
+            mark(food.position(), CELL_FOOD);
+        }
+        for snake in &self.snakes {
+            for segment in snake.segments() {
+                mark(segment, CELL_BODY);
+            }
+            mark(snake.tail_position(), CELL_TAIL);
+            mark(snake.head_position(), CELL_HEAD);
+        }
+        rows
+    }
+
+    /// Takes a snapshot of the current run, so that it can be saved.
+    pub fn snapshot(&self) -> SavedGame {
+        SavedGame {
+            board: self.board.clone(),
+            snakes: self.snakes.clone(),
+            food: self.foods[0].clone(),
+            extra_foods: self.foods[1..].to_vec(),
+            bonus_food: self.bonus_food.clone(),
+            poison_food: self.poison_food.clone(),
+            scores: self.scores.clone(),
+            elapsed: self.elapsed,
+        }
+    }
+
+    /// Restores the run from a saved snapshot.
+    ///
+    /// Fails (leaving the game untouched) if the save was made on a different
+    /// board or with a different number of players, or if it's corrupt (see
+    /// `Snake::validate`, and every food must be on the board too).
+    pub fn restore(&mut self, saved: SavedGame) -> SnakeResult {
+        if saved.board != self.board {
+            return Err(SnakeError::SaveError(
+                "The save was made on a different board".to_owned(),
+            ));
+        }
+        if saved.snakes.len() != self.snakes.len() || saved.scores.len() != self.snakes.len() {
+            return Err(SnakeError::SaveError(
+                "The save was made with a different number of players".to_owned(),
+            ));
+        }
+        for snake in &saved.snakes {
+            snake.validate(&self.board, &self.portals)?;
+        }
+        let foods = std::iter::once(&saved.food)
+            .chain(&saved.extra_foods)
+            .chain(&saved.bonus_food)
+            .chain(&saved.poison_food);
+        if let Some(food) = foods
+            .map(Food::position)
+            .find(|position| !self.board.contains(*position))
+        {
+            return Err(SnakeError::SaveError(format!(
+                "The food is off the board at ({}, {})",
+                food.x, food.y
+            )));
+        }
+        self.snakes = saved.snakes;
+        for snake in &mut self.snakes {
+            snake.track_occupancy(&self.board);
+        }
+        self.foods = std::iter::once(saved.food)
+            .chain(saved.extra_foods)
+            .collect();
+        self.bonus_food = saved.bonus_food;
+        self.poison_food = saved.poison_food;
+        self.scores = saved.scores;
+        // the combos, deaths and power-ups aren't saved, so the restored run starts without any
+        self.deaths = vec![0; self.snakes.len()];
+        self.power_up_item = None;
+        self.active_power_up = None;
+        self.combos = vec![0; self.snakes.len()];
+        self.ticks_since_food = vec![0; self.snakes.len()];
+        self.elapsed = saved.elapsed;
+        self.recording = None;
+        Ok(())
+    }
+
+    /// Helper function that starts the given player's snake over (in practice), as
+    /// short as a snake gets, where it has room to move in the starting direction:
+    /// at its start position if it's free, otherwise at the first free cell that fits.
+    ///
+    /// Returns `false` (leaving the snake untouched) if there's no room anywhere.
+    fn respawn(&mut self, player: Player) -> bool {
+        let board = &self.board;
+        let direction = self.start_direction;
+        let occupied = self.occupied_cells();
+        // the tail, the head and the cell ahead of it must all be free
+        let fits = |head: GridPosition| {
+            [
+                board.step(head, direction.inverse()),
+                Some(head),
+                board.step(head, direction),
+            ]
+            .iter()
+            .all(|cell| {
+                cell.is_some_and(|cell| !board.is_obstacle(cell) && !occupied.contains(&cell))
+            })
+        };
+        let start = match (player, self.level_start) {
+            (Player::One, Some(position)) => position,
+            _ => start_position(board, direction, player),
+        };
+        let position = std::iter::once(start)
+            .chain(board.cells())
+            .find(|head| fits(*head));
+        let position = match position {
+            Some(position) => position,
+            None => return false,
+        };
+        let index = player.index();
+        self.snakes[index].reset_to(position, direction, &self.board);
+        self.deaths[index] += 1;
+        self.combos[index] = 0;
+        self.ticks_since_food[index] = 0;
+        true
+    }
+
+    /// Helper function that generates a new random
+    /// position for a food while ensuring that it
+    /// doesn't collide with any snake, any other food or any portal.
+    ///
+    /// With the safe spawn option, the cells around the heads of the snakes are
+    /// avoided as well, unless that leaves no free cells (e.g. on a crowded board).
+    ///
+    /// Returns `None` if there are no free cells left.
+    fn generate_food_position(&mut self) -> Option<GridPosition> {
+        let occupied = self.occupied_cells();
+        if self.safe_spawn {
+            let mut avoided = occupied.clone();
+            avoided.extend(self.safe_zone());
+            if let Some(position) = self.board.random_free_cell(&mut self.rng, &avoided) {
+                return Some(position);
+            }
+        }
+        self.board.random_free_cell(&mut self.rng, &occupied)
+    }
+
+    /// Helper function that returns the cells within `SAFE_SPAWN_RADIUS` of the
+    /// head of any snake (wrapping around the edges of the board, if they do).
+    fn safe_zone(&self) -> Vec<GridPosition> {
+        let (width, height) = self.board.size();
+        let edges = self.board.edges;
+        let radius = SAFE_SPAWN_RADIUS;
+        self.snakes
+            .iter()
+            .map(Snake::head_position)
+            .flat_map(|head| {
+                (-radius..=radius).flat_map(move |dy| {
+                    (-radius..=radius).map(move |dx| (head.x + dx, head.y + dy))
+                })
+            })
+            .filter_map(|(x, y)| {
+                let axis = |value: i16, size: i16, wraps: bool| {
+                    if wraps {
+                        Some(value.modulo_signed(size))
+                    } else {
+                        Some(value).filter(|value| (0..size).contains(value))
+                    }
+                };
+                Some(GridPosition::new(
+                    axis(x, width, edges.wraps_x())?,
+                    axis(y, height, edges.wraps_y())?,
+                ))
+            })
+            .collect()
+    }
+
+    /// Helper function that returns the cells that a food can't be on: the
+    /// cells of the snakes, of all of the foods, of the power-up and of the portals.
+    fn occupied_cells(&self) -> Vec<GridPosition> {
+        let mut occupied: Vec<GridPosition> =
+            self.snakes.iter().flat_map(Snake::occupied_cells).collect();
+        occupied.extend(self.foods.iter().map(Food::position));
+        occupied.extend(self.bonus_food.as_ref().map(Food::position));
+        occupied.extend(self.poison_food.as_ref().map(Food::position));
+        occupied.extend(self.power_up_item.as_ref().map(PowerUpItem::position));
+        occupied.extend(
+            self.portals
+                .iter()
+                .flat_map(|&(entrance, exit)| vec![entrance, exit]),
+        );
+        occupied
+    }
+
+    /// Helper function that handles the snake at `index` eating the food
+    /// it's currently on (which is worth `points`), respawning it as needed.
+    fn eat_food(&mut self, index: usize, points: u32) -> StepResult {
+        self.scores[index] += points;
+        // eating already grew the snake by one segment
+        self.snakes[index].grow(self.growth - 1);
+
+        // the bonus food simply disappears once eaten
+        let head = self.snakes[index].head_position();
+        let ate_bonus_food = self
+            .bonus_food
+            .as_ref()
+            .is_some_and(|bonus_food| bonus_food.position() == head);
+        if ate_bonus_food {
+            self.bonus_food = None;
+        }
+
+        let length: usize = self.snakes.iter().map(Snake::len).sum();
+        if length >= self.board.playable_cells() {
+            // the snakes fill the whole board, so the game is won
+            return StepResult::Won;
+        }
+        if ate_bonus_food {
+            return StepResult::Ate(points);
+        }
+        // only the food that was eaten respawns, the others stay where they are
+        let eaten = self
+            .foods
+            .iter()
+            .position(|food| food.position() == head)
+            .unwrap_or(0);
+        match self.generate_food_position() {
+            Some(position) => self.foods[eaten].set_position(position),
+            // the only free cell left is taken by the bonus (or poison) food, so it makes room
+            None => {
+                if let Some(other_food) = self.bonus_food.take().or_else(|| self.poison_food.take())
+                {
+                    self.foods[eaten].set_position(other_food.position());
+                } else if self.foods.len() > 1 {
+                    // there's no room left for it, but the other foods are still around
+                    self.foods.remove(eaten);
+                }
+            }
+        }
+
+        // every now and then, spawn a bonus food as well
+        if self.bonus_food.is_none() && self.rng.gen_bool(BONUS_FOOD_CHANCE) {
+            self.bonus_food = self.generate_food_position().map(Food::new_bonus);
+        }
+        // and a poison food (more rarely)
+        if self.poison_food.is_none() && self.rng.gen_bool(POISON_FOOD_CHANCE) {
+            self.poison_food = self.generate_food_position().map(Food::new_poison);
+        }
+        // and a power-up (if enabled, and there isn't one already)
+        if self.power_ups
+            && self.power_up_item.is_none()
+            && self.active_power_up.is_none()
+            && self.rng.gen_bool(POWER_UP_CHANCE)
+        {
+            let power_up = *PowerUp::ALL.choose(&mut self.rng).unwrap();
+            self.power_up_item = self
+                .generate_food_position()
+                .map(|position| PowerUpItem::new(power_up, position));
+        }
+        StepResult::Ate(points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::*;
+
+    fn seeded_config(seed: u64) -> GameConfig {
+        GameConfig {
+            seed: Some(seed),
+            ..GameConfig::default()
+        }
+    }
+
+    #[test]
+    fn stepping_moves_the_snake() {
+        let mut game = Game::new(&seeded_config(0));
+        assert_eq!(game.step_logic(), StepResult::Moved);
+        assert_eq!(game.snake().head_position(), GridPosition::new(2, 0));
+        assert_eq!(game.score(), 0);
+    }
+
+    #[test]
+    fn buffered_turns_are_applied_one_per_step() {
+        let mut game = Game::new(&GameConfig {
+            edges: EdgeBehavior::Wrap,
+            ..seeded_config(0)
+        });
+        let head = game.snake().head_position();
+        game.set_direction(Direction::Down).unwrap();
+        game.set_direction(Direction::Left).unwrap();
+        // a third turn doesn't fit in the buffer
+        assert!(game.set_direction(Direction::Up).is_err());
+        game.step_logic();
+        assert_eq!(
+            game.snake().head_position(),
+            GridPosition::new(head.x, head.y + 1)
+        );
+        game.step_logic();
+        assert_eq!(
+            game.snake().head_position(),
+            GridPosition::new(head.x - 1, head.y + 1)
+        );
+        // and a driver that steers before every step gets its turn right away
+        game.set_direction(Direction::Down).unwrap();
+        game.step_logic();
+        assert_eq!(
+            game.snake().head_position(),
+            GridPosition::new(head.x - 1, head.y + 2)
+        );
+    }
+
+    #[test]
+    fn snakes_start_in_the_configured_direction() {
+        let mut game = Game::new(&GameConfig {
+            start_direction: Direction::Up,
+            ..two_player_config()
+        });
+        let (width, height) = game.board.size();
+        assert_eq!(
+            game.snakes[0].head_position(),
+            GridPosition::new(0, height - 2)
+        );
+        assert_eq!(
+            game.snakes[1].head_position(),
+            GridPosition::new(width - 1, height - 2)
+        );
+        assert_eq!(game.step_logic(), StepResult::Moved);
+        assert_eq!(
+            game.snake().head_position(),
+            GridPosition::new(0, height - 3)
+        );
+    }
+
+    #[test]
+    fn eating_the_food_scores_and_respawns_it() {
+        let mut game = Game::new(&seeded_config(0));
+        game.foods[0].set_position(GridPosition::new(2, 0));
+        assert_eq!(game.step_logic(), StepResult::Ate(POINTS_PER_FOOD));
+        assert_eq!(game.score(), POINTS_PER_FOOD);
+        assert_ne!(game.food().position(), GridPosition::new(2, 0));
+        assert!(!game
+            .snake()
+            .occupied_cells()
+            .contains(&game.food().position()));
+    }
+
+    #[test]
+    fn only_the_eaten_food_respawns() {
+        let mut game = Game::new(&GameConfig {
+            food_count: 3,
+            ..seeded_config(0)
+        });
+        let positions: Vec<GridPosition> = game.foods().iter().map(Food::position).collect();
+        assert_eq!(positions.len(), 3);
+        assert!(positions[0] != positions[1] && !positions[..2].contains(&positions[2]));
+
+        game.foods[1].set_position(GridPosition::new(2, 0));
+        let others = [game.foods[0].position(), game.foods[2].position()];
+        assert_eq!(game.step_logic(), StepResult::Ate(POINTS_PER_FOOD));
+        assert_eq!(game.foods().len(), 3);
+        assert_eq!([game.foods[0].position(), game.foods[2].position()], others);
+        let respawned = game.foods[1].position();
+        assert!(respawned != GridPosition::new(2, 0) && !others.contains(&respawned));
+
+        // and all of them are saved
+        let mut restored = Game::new(&seeded_config(1));
+        restored.restore(game.snapshot()).unwrap();
+        let food_positions =
+            |game: &Game| game.foods().iter().map(Food::position).collect::<Vec<_>>();
+        assert_eq!(food_positions(&restored), food_positions(&game));
+    }
+
+    #[test]
+    fn corrupt_saves_arent_restored() {
+        let game = Game::new(&GameConfig {
+            start_length: 4,
+            ..seeded_config(0)
+        });
+        let mut restored = Game::new(&seeded_config(1));
+        let restore = |restored: &mut Game, saved| match restored.restore(saved) {
+            Err(SnakeError::SaveError(message)) => message,
+            result => panic!("Expected a save error, got {:?}", result),
+        };
+
+        let mut saved = game.snapshot();
+        saved.food.set_position(GridPosition::new(GRID_SIZE.0, 0));
+        assert!(restore(&mut restored, saved).starts_with("The food is off the board"));
+
+        // the snakes are edited through their JSON, since their parts are private
+        let edited = |edit: &dyn Fn(&mut serde_json::Value)| {
+            let mut saved = game.snapshot();
+            let mut snake = serde_json::to_value(&saved.snakes[0]).unwrap();
+            edit(&mut snake);
+            saved.snakes[0] = serde_json::from_value(snake).unwrap();
+            saved
+        };
+        let saved = edited(&|snake| snake["tail"]["position"]["y"] = (-1).into());
+        assert!(restore(&mut restored, saved).starts_with("The snake is off the board"));
+        let far = (game.snake().head_position().x + GRID_SIZE.0 / 2) % GRID_SIZE.0;
+        let saved = edited(&|snake| snake["body"][0]["position"]["x"] = far.into());
+        assert!(restore(&mut restored, saved).starts_with("The snake is broken"));
+        let saved = edited(&|snake| {
+            snake["body"][0]["sprite"] = serde_json::json!({"Segment": ["Up", "Up"]})
+        });
+        assert_eq!(
+            restore(&mut restored, saved),
+            "The snake has an invalid sprite"
+        );
+
+        // and the game is left untouched
+        assert_eq!(restored.snake().len(), START_LENGTH);
+        restored.restore(game.snapshot()).unwrap();
+        assert_eq!(restored.snake().len(), 4);
+    }
+
+    #[test]
+    fn snakes_that_dont_eat_starve() {
+        let mut game = Game::new(&GameConfig {
+            starvation: Some(3),
+            ..seeded_config(0)
+        });
+        assert_eq!(game.hunger_left(Player::One), Some(1.0));
+        for _ in 0..3 {
+            assert_eq!(game.step_logic(), StepResult::Moved);
+        }
+        assert_eq!(game.hunger_left(Player::One), Some(0.0));
+        assert_eq!(game.step_logic(), StepResult::Died);
+
+        // but eating fills it up again
+        let mut game = Game::new(&GameConfig {
+            starvation: Some(3),
+            ..seeded_config(0)
+        });
+        game.step_logic();
+        let head = game.snake().head_position();
+        game.foods[0].set_position(GridPosition::new(head.x + 1, head.y));
+        assert_eq!(game.step_logic(), StepResult::Ate(POINTS_PER_FOOD));
+        assert_eq!(game.hunger_left(Player::One), Some(1.0));
+        assert_eq!(Game::new(&seeded_config(0)).hunger_left(Player::One), None);
+    }
+
+    #[test]
+    fn each_food_grows_the_snake_by_the_growth() {
+        let mut game = Game::new(&GameConfig {
+            growth: 3,
+            ..seeded_config(0)
+        });
+        game.foods[0].set_position(GridPosition::new(2, 0));
+        assert_eq!(game.step_logic(), StepResult::Ate(POINTS_PER_FOOD));
+        game.foods[0].set_position(GridPosition::new(0, 10));
+        game.bonus_food = None;
+        game.poison_food = None;
+        for _ in 0..3 {
+            assert_eq!(game.step_logic(), StepResult::Moved);
+        }
+        assert_eq!(game.snake().len(), START_LENGTH + 3);
+    }
+
+    #[test]
+    fn eating_quickly_builds_a_combo() {
+        let mut game = Game::new(&seeded_config(0));
+        game.foods[0].set_position(GridPosition::new(2, 0));
+        assert_eq!(game.step_logic(), StepResult::Ate(POINTS_PER_FOOD));
+        game.foods[0].set_position(GridPosition::new(3, 0));
+        assert_eq!(game.step_logic(), StepResult::Ate(POINTS_PER_FOOD * 2));
+        assert_eq!(game.combos(), &[2]);
+
+        // keep the food (and any bonus food) out of the way until the combo is over
+        game.foods[0].set_position(GridPosition::new(0, 10));
+        game.bonus_food = None;
+        game.poison_food = None;
+        for _ in 0..=COMBO_WINDOW {
+            assert_eq!(game.step_logic(), StepResult::Moved);
+        }
+        assert_eq!(game.combos(), &[0]);
+        let head = game.snake().head_position();
+        game.foods[0].set_position(game.board.step(head, Direction::Right).unwrap());
+        assert_eq!(game.step_logic(), StepResult::Ate(POINTS_PER_FOOD));
+    }
+
+    #[test]
+    fn safe_spawn_keeps_food_away_from_the_head() {
+        let mut game = Game::new(&GameConfig {
+            width: 6,
+            height: 6,
+            safe_spawn: true,
+            ..seeded_config(0)
+        });
+        let head = game.snake().head_position();
+        assert_eq!(game.safe_zone().len(), 9);
+        // the head is on the top edge, so the zone wraps around to the bottom one
+        assert!(game.safe_zone().contains(&GridPosition::new(head.x, 5)));
+        for _ in 0..50 {
+            let position = game.generate_food_position().unwrap();
+            assert!((position.x - head.x).abs() > 1 || (position.y - head.y).abs() > 1);
+        }
+    }
+
+    #[test]
+    fn safe_spawn_falls_back_to_any_free_cell() {
+        let mut game = Game::new(&GameConfig {
+            width: 4,
+            height: 4,
+            safe_spawn: true,
+            ..seeded_config(0)
+        });
+        // only the cell right in front of the head is left
+        let ahead = GridPosition::new(2, 0);
+        game.foods[0].set_position(GridPosition::new(3, 3));
+        game.board.obstacles = game
+            .board
+            .cells()
+            .filter(|cell| {
+                *cell != ahead
+                    && !game.snakes[0].occupies(*cell)
+                    && *cell != GridPosition::new(3, 3)
+            })
+            .collect();
+        assert_eq!(game.generate_food_position(), Some(ahead));
+    }
+
+    #[test]
+    fn power_ups_only_spawn_when_enabled() {
+        for power_ups in &[false, true] {
+            let mut game = Game::new(&GameConfig {
+                power_ups: *power_ups,
+                ..seeded_config(1)
+            });
+            for _ in 0..100 {
+                game.eat_food(0, POINTS_PER_FOOD);
+            }
+            assert_eq!(game.power_up_item().is_some(), *power_ups);
+        }
+    }
+
+    #[test]
+    fn the_magnet_pulls_the_food_towards_the_head() {
+        let mut game = Game::new(&GameConfig {
+            power_ups: true,
+            ..seeded_config(0)
+        });
+        let head = game.snake().head_position();
+        let item = PowerUpItem::new(PowerUp::Magnet, GridPosition::new(head.x + 1, head.y));
+        game.power_up_item = Some(item);
+        game.foods[0].set_position(GridPosition::new(head.x + 1, head.y + 6));
+        assert_eq!(game.step_logic(), StepResult::Moved);
+        assert!(game.power_up_item().is_none());
+        let active = game.active_power_up().unwrap();
+        assert_eq!(active.player, Player::One);
+        assert_eq!(active.power_up, PowerUp::Magnet);
+
+        let head = game.snake().head_position();
+        let before = game.board.distance(game.food().position(), head);
+        // the food moves before the snake does
+        game.step_logic();
+        assert!(game.board.distance(game.food().position(), head) < before);
+        assert!(game.active_power_up().unwrap().fraction_left() < 1.0);
+    }
+
+    #[test]
+    fn the_speed_boost_shortens_the_ticks_for_a_while() {
+        let mut game = Game::new(&seeded_config(0));
+        let interval = game.update_interval();
+        game.active_power_up = Some(ActivePowerUp {
+            power_up: PowerUp::Speed,
+            player: Player::One,
+            ticks_left: 2,
+        });
+        assert_eq!(game.update_interval(), interval.div_f64(SPEED_BOOST_FACTOR));
+        game.set_slow_motion(true);
+        assert_eq!(
+            game.update_interval(),
+            interval.div_f64(SPEED_BOOST_FACTOR) * SLOW_MOTION_FACTOR
+        );
+        game.set_slow_motion(false);
+        game.step_logic();
+        game.step_logic();
+        assert!(!game.has_power_up(PowerUp::Speed));
+        assert_eq!(game.update_interval(), interval);
+    }
+
+    #[test]
+    fn the_shield_protects_the_snake_that_picked_it_up() {
+        let mut game = Game::new(&GameConfig {
+            edges: EdgeBehavior::Wall,
+            ..seeded_config(0)
+        });
+        game.active_power_up = Some(ActivePowerUp {
+            power_up: PowerUp::Shield,
+            player: Player::One,
+            ticks_left: 2,
+        });
+        game.set_direction(Direction::Up).unwrap();
+        // the snake starts on the top edge, so it goes through the wall
+        assert_eq!(game.step_logic(), StepResult::Moved);
+        assert!(game.snake().is_shielded());
+        game.step_logic();
+        assert!(!game.snake().is_shielded());
+    }
+
+    #[test]
+    fn the_board_is_dumped_as_ascii() {
+        let mut game = Game::new(&GameConfig {
+            width: 6,
+            height: 4,
+            ..seeded_config(0)
+        });
+        game.snakes[0] = Snake::new((3, 1).into(), Direction::Right, 4, &game.board);
+        game.foods[0].set_position(GridPosition::new(5, 2));
+        game.bonus_food = None;
+        game.poison_food = None;
+        assert_eq!(game.debug_board(), "......\ntooH..\n.....*\n......");
+    }
+
+    #[test]
+    fn the_board_is_encoded_as_numbers() {
+        let mut game = Game::new(&GameConfig {
+            width: 5,
+            height: 3,
+            ..seeded_config(0)
+        });
+        game.snakes[0] = Snake::new((2, 1).into(), Direction::Right, 3, &game.board);
+        game.board.obstacles = vec![GridPosition::new(4, 0)];
+        game.foods[0].set_position(GridPosition::new(3, 2));
+        game.bonus_food = None;
+        game.poison_food = None;
+        assert_eq!(
+            game.board_tensor(),
+            vec![
+                vec![0, 0, 0, 0, 5],
+                vec![3, 1, 2, 0, 0],
+                vec![0, 0, 0, 4, 0]
+            ]
+        );
+    }
+
+    #[test]
+    fn food_never_spawns_under_the_tail() {
+        // the snake fills the only row that isn't walled off, its tail included
+        let mut game = Game::new(&GameConfig {
+            width: 4,
+            height: 4,
+            start_length: 4,
+            ..seeded_config(0)
+        });
+        game.board.obstacles = game.board.cells().filter(|cell| cell.y > 0).collect();
+        assert_eq!(game.generate_food_position(), None);
+    }
+
+    #[test]
+    fn food_never_spawns_on_a_portal() {
+        // the only free cells are the portals (and the food itself)
+        let mut game = Game::new(&GameConfig {
+            width: 4,
+            height: 4,
+            portals: true,
+            ..seeded_config(0)
+        });
+        let (entrance, exit) = game.board.default_portals();
+        let food = game.food().position();
+        game.board.obstacles = game
+            .board
+            .cells()
+            .filter(|cell| ![entrance, exit, food].contains(cell) && !game.snake().occupies(*cell))
+            .collect();
+        assert_eq!(game.generate_food_position(), None);
+    }
+
+    #[test]
+    fn slow_motion_doesnt_slow_the_game_time() {
+        let mut game = Game::new(&GameConfig {
+            mode: GameMode::TimeAttack {
+                limit: Duration::from_secs(1),
+            },
+            ..seeded_config(0)
+        });
+        let interval = game.update_interval();
+        game.set_slow_motion(true);
+        assert_eq!(game.update_interval(), interval * SLOW_MOTION_FACTOR);
+        game.step_logic();
+        assert_eq!(game.time_left(), Some(Duration::from_secs(1) - interval));
+        game.set_slow_motion(false);
+        assert_eq!(game.update_interval(), interval);
+    }
+
+    #[test]
+    fn step_requests_are_taken_once() {
+        let mut game = Game::new(&seeded_config(0));
+        assert!(!game.take_step_request());
+        game.request_step();
+        assert!(game.take_step_request());
+        assert!(!game.take_step_request());
+    }
+
+    #[test]
+    fn time_attack_ends_when_the_time_is_up() {
+        let mut game = Game::new(&GameConfig {
+            mode: GameMode::TimeAttack {
+                limit: Duration::from_secs(1),
+            },
+            ..seeded_config(0)
+        });
+        let interval = game.update_interval();
+        assert_eq!(game.time_left(), Some(Duration::from_secs(1)));
+        assert_eq!(game.step_logic(), StepResult::Moved);
+        assert_eq!(game.time_left(), Some(Duration::from_secs(1) - interval));
+        let mut result = StepResult::Moved;
+        while result == StepResult::Moved {
+            result = game.step_logic();
+        }
+        assert_eq!(result, StepResult::TimeUp);
+        assert_eq!(game.time_left(), Some(Duration::default()));
+        assert_eq!(Game::new(&seeded_config(0)).time_left(), None);
+    }
+
+    #[test]
+    fn practice_starts_the_snake_over_instead_of_dying() {
+        let mut game = Game::new(&GameConfig {
+            edges: EdgeBehavior::Wall,
+            mode: GameMode::Practice,
+            start_length: 4,
+            ..seeded_config(0)
+        });
+        let food = game.food().position();
+        game.set_direction(Direction::Up).unwrap();
+        assert_eq!(game.step_logic(), StepResult::Respawned(Player::One));
+        assert_eq!(game.deaths(), &[1]);
+        assert_eq!(game.snake().len(), START_LENGTH);
+        assert_eq!(game.snake().next_direction(), Direction::Right);
+        assert_eq!(game.food().position(), food);
+        assert_eq!(game.step_logic(), StepResult::Moved);
+    }
+
+    #[test]
+    fn levels_place_the_snake_and_the_food() {
+        let level = Level::parse("#######\n#..S..#\n#.....#\n#F....#\n#######").unwrap();
+        let game = Game::new(&GameConfig {
+            width: level.width,
+            height: level.height,
+            level: Some(level),
+            ..seeded_config(0)
+        });
+        assert_eq!(game.snake().head_position(), GridPosition::new(3, 1));
+        assert_eq!(game.food().position(), GridPosition::new(1, 3));
+        assert!(game.board().is_obstacle(GridPosition::new(0, 0)));
+        assert_eq!(
+            game.debug_board(),
+            "#######\n#.tH..#\n#.....#\n#*....#\n#######"
+        );
+    }
+
+    /// A `GameObserver` that records all of the events, which are
+    /// shared with the test (since the `Game` owns the observer).
+    #[derive(Default)]
+    struct RecordingObserver {
+        events: std::rc::Rc<std::cell::RefCell<Vec<String>>>,
+    }
+
+    impl GameObserver for RecordingObserver {
+        fn on_eat(&mut self, player: Player, points: u32) {
+            self.events
+                .borrow_mut()
+                .push(format!("{:?} ate {}", player, points));
+        }
+
+        fn on_death(&mut self, player: Player) {
+            self.events.borrow_mut().push(format!("{:?} died", player));
+        }
+
+        fn on_turn(&mut self, player: Player, direction: Direction) {
+            self.events
+                .borrow_mut()
+                .push(format!("{:?} turned {:?}", player, direction));
+        }
+    }
+
+    #[test]
+    fn observers_are_notified_of_the_events() {
+        let mut game = Game::new(&GameConfig {
+            edges: EdgeBehavior::Wall,
+            ..seeded_config(0)
+        });
+        let observer = RecordingObserver::default();
+        let events = observer.events.clone();
+        game.set_observer(Box::new(observer));
+
+        let head = game.snake().head_position();
+        game.foods[0].set_position(GridPosition::new(head.x + 1, head.y));
+        game.bonus_food = None;
+        game.poison_food = None;
+        assert_eq!(game.step_logic(), StepResult::Ate(POINTS_PER_FOOD));
+        // turning the way the snake already goes doesn't count
+        game.set_direction(Direction::Right).unwrap();
+        game.set_direction(Direction::Up).unwrap();
+        assert_eq!(game.step_logic(), StepResult::Died);
+        assert_eq!(
+            *events.borrow(),
+            vec!["One ate 1", "One turned Up", "One died"]
+        );
+        assert!(game.take_observer().is_some());
+    }
+
+    #[test]
+    fn eating_poison_at_the_minimum_length_is_death() {
+        let mut game = Game::new(&seeded_config(0));
+        game.poison_food = Some(Food::new_poison(GridPosition::new(2, 0)));
+        assert_eq!(game.step_logic(), StepResult::Died);
+    }
+
+    #[test]
+    fn eaten_poison_is_gone() {
+        let mut game = Game::new(&GameConfig {
+            start_length: 4,
+            ..seeded_config(0)
+        });
+        game.poison_food = Some(Food::new_poison(GridPosition::new(2, 0)));
+        assert_eq!(game.step_logic(), StepResult::Moved);
+        assert_eq!(game.snake().len(), 3);
+        assert!(game.poison_food().is_none());
+    }
+
+    #[test]
+    fn crashing_into_a_wall_is_death() {
+        let mut game = Game::new(&GameConfig {
+            edges: EdgeBehavior::Wall,
+            ..seeded_config(0)
+        });
+        game.set_direction(Direction::Up).unwrap();
+        assert_eq!(game.step_logic(), StepResult::Died);
+    }
+
+    #[test]
+    fn builder_defaults_to_the_default_game() {
+        let built = GameBuilder::new().seed(3).build().unwrap();
+        let game = Game::new(&seeded_config(3));
+        assert_eq!(built.board().size(), game.board().size());
+        assert_eq!(built.snake().head_position(), game.snake().head_position());
+        assert_eq!(built.food().position(), game.food().position());
+    }
+
+    #[test]
+    fn builder_starts_from_a_config() {
+        let config = GameConfig::from_args(vec!["--foods".to_owned(), "3".to_owned()]).unwrap();
+        let game = GameBuilder::from_config(config).seed(3).build().unwrap();
+        assert_eq!(game.foods().len(), 3);
+    }
+
+    #[test]
+    fn builder_applies_and_validates_its_settings() {
+        let game = Game::builder()
+            .size(10, 6)
+            .edges(EdgeBehavior::Wall)
+            .start_length(4)
+            .start_direction(Direction::Left)
+            .build()
+            .unwrap();
+        assert_eq!(game.board().size(), (10, 6));
+        assert_eq!(game.snake().len(), 4);
+        assert_eq!(game.snake().next_direction(), Direction::Left);
+        assert!(Game::builder().size(2, 6).build().is_err());
+        assert!(Game::builder()
+            .start_length(11)
+            .size(10, 6)
+            .build()
+            .is_err());
+        assert!(Game::builder()
+            .start_length(8)
+            .start_direction(Direction::Up)
+            .size(10, 6)
+            .build()
+            .is_err());
+    }
+
+    fn two_player_config() -> GameConfig {
+        GameConfig {
+            two_players: true,
+            ..seeded_config(0)
+        }
+    }
+
+    #[test]
+    fn each_player_steers_their_own_snake() {
+        let mut game = Game::new(&two_player_config());
+        game.set_player_direction(Player::Two, Direction::Up)
+            .unwrap();
+        assert_eq!(game.step_logic(), StepResult::Moved);
+        assert_eq!(game.snakes()[0].head_position(), GridPosition::new(2, 0));
+        assert_eq!(game.snakes()[1].head_position(), GridPosition::new(1, 18));
+        assert!(Game::new(&seeded_config(0))
+            .set_player_direction(Player::Two, Direction::Up)
+            .is_err());
+    }
+
+    #[test]
+    fn running_into_the_other_snake_loses() {
+        let mut game = Game::new(&two_player_config());
+        // player two moves up into the cell that player one's head just left
+        game.snakes[1] = Snake::new((1, 1).into(), Direction::Right, START_LENGTH, &game.board);
+        game.set_player_direction(Player::Two, Direction::Up)
+            .unwrap();
+        assert_eq!(game.step_logic(), StepResult::PlayerLost(Player::Two));
+    }
+
+    #[test]
+    fn heads_colliding_is_a_draw() {
+        let mut game = Game::new(&two_player_config());
+        // both heads move into (2, 0) at the same time
+        game.snakes[1] = Snake::new((2, 1).into(), Direction::Right, START_LENGTH, &game.board);
+        game.set_player_direction(Player::Two, Direction::Up)
+            .unwrap();
+        assert_eq!(game.step_logic(), StepResult::Draw);
+    }
+
+    #[test]
+    fn seeded_games_are_reproducible() {
+        let play = |seed| {
+            let mut game = Game::new(&seeded_config(seed));
+            let mut positions = Vec::new();
+            for _ in 0..20 {
+                // keep feeding the snake so that the food keeps respawning
+                let head = game.snake().head_position();
+                let next = game.board().step(head, Direction::Right).unwrap();
+                game.foods[0].set_position(next);
+                assert!(matches!(game.step_logic(), StepResult::Ate(_)));
+                positions.push(game.food().position());
+            }
+            positions
+        };
+        assert_eq!(play(7), play(7));
+    }
+}
@@ -0,0 +1,275 @@
+use crate::primitives::*;
+use ggez::{event::KeyCode, filesystem, Context};
+use log::warn;
+use serde::Deserialize;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The name of the file (inside the user config directory)
+/// that the key bindings are stored in.
+const KEYS_FILE: &str = "keys.toml";
+
+/// The names of the keys that can be bound, as they're written in the key bindings file.
+///
+/// `KeyCode` can't be (de)serialized by itself, so this maps between the two.
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("a", KeyCode::A),
+    ("b", KeyCode::B),
+    ("c", KeyCode::C),
+    ("d", KeyCode::D),
+    ("e", KeyCode::E),
+    ("f", KeyCode::F),
+    ("g", KeyCode::G),
+    ("h", KeyCode::H),
+    ("i", KeyCode::I),
+    ("j", KeyCode::J),
+    ("k", KeyCode::K),
+    ("l", KeyCode::L),
+    ("m", KeyCode::M),
+    ("n", KeyCode::N),
+    ("o", KeyCode::O),
+    ("p", KeyCode::P),
+    ("q", KeyCode::Q),
+    ("r", KeyCode::R),
+    ("s", KeyCode::S),
+    ("t", KeyCode::T),
+    ("u", KeyCode::U),
+    ("v", KeyCode::V),
+    ("w", KeyCode::W),
+    ("x", KeyCode::X),
+    ("y", KeyCode::Y),
+    ("z", KeyCode::Z),
+    ("0", KeyCode::Key0),
+    ("1", KeyCode::Key1),
+    ("2", KeyCode::Key2),
+    ("3", KeyCode::Key3),
+    ("4", KeyCode::Key4),
+    ("5", KeyCode::Key5),
+    ("6", KeyCode::Key6),
+    ("7", KeyCode::Key7),
+    ("8", KeyCode::Key8),
+    ("9", KeyCode::Key9),
+    ("up", KeyCode::Up),
+    ("down", KeyCode::Down),
+    ("left", KeyCode::Left),
+    ("right", KeyCode::Right),
+    ("space", KeyCode::Space),
+    ("return", KeyCode::Return),
+    ("escape", KeyCode::Escape),
+    ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Back),
+    ("pause", KeyCode::Pause),
+    ("numpad2", KeyCode::Numpad2),
+    ("numpad4", KeyCode::Numpad4),
+    ("numpad6", KeyCode::Numpad6),
+    ("numpad8", KeyCode::Numpad8),
+];
+
+/// Returns the name of the given key in the key bindings file,
+/// or `None` if it can't be bound.
+pub fn key_name(key: KeyCode) -> Option<&'static str> {
+    KEY_NAMES
+        .iter()
+        .find(|(_, code)| *code == key)
+        .map(|(name, _)| *name)
+}
+
+/// Returns the (uppercase) character that the given key types
+/// into the initials of a `Scoreboard` entry, if it's a letter or a digit.
+pub fn initial_for_key(key: KeyCode) -> Option<char> {
+    let mut chars = key_name(key)?.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_alphanumeric() => Some(c.to_ascii_uppercase()),
+        _ => None,
+    }
+}
+
+/// Returns the key with the given name in the key bindings
+/// file (ignoring case), or `None` if there's no such key.
+pub fn key_from_name(name: &str) -> Option<KeyCode> {
+    KEY_NAMES
+        .iter()
+        .find(|(key_name, _)| key_name.eq_ignore_ascii_case(name))
+        .map(|(_, code)| *code)
+}
+
+/// The keys that the player's actions are bound to, which
+/// are loaded from a file in the user config directory.
+///
+/// Player two (in a two-player game) always steers with WASD.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KeyBindings {
+    pub up: KeyCode,
+    pub down: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub pause: KeyCode,
+    /// Starts the game from the menu, and restarts it once it's over.
+    pub restart: KeyCode,
+    /// Opens and closes the leaderboard (between games).
+    pub leaderboard: KeyCode,
+    pub quit: KeyCode,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            up: KeyCode::Up,
+            down: KeyCode::Down,
+            left: KeyCode::Left,
+            right: KeyCode::Right,
+            pause: KeyCode::Space,
+            restart: KeyCode::Return,
+            leaderboard: KeyCode::L,
+            quit: KeyCode::Escape,
+        }
+    }
+}
+
+/// The key bindings as they're written in the file: every action is
+/// optional and bound to a key name (see `key_from_name`).
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct KeyNames {
+    up: Option<String>,
+    down: Option<String>,
+    left: Option<String>,
+    right: Option<String>,
+    pause: Option<String>,
+    restart: Option<String>,
+    leaderboard: Option<String>,
+    quit: Option<String>,
+}
+
+impl KeyBindings {
+    /// Returns the path of the key bindings file in the user config directory.
+    pub fn path(ctx: &Context) -> PathBuf {
+        filesystem::user_config_dir(ctx).join(KEYS_FILE)
+    }
+
+    /// Returns the `Direction` that the given key is bound to (if any).
+    pub fn direction(&self, key: KeyCode) -> Option<Direction> {
+        match key {
+            key if key == self.up => Some(Direction::Up),
+            key if key == self.down => Some(Direction::Down),
+            key if key == self.left => Some(Direction::Left),
+            key if key == self.right => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    /// Parses the key bindings from the contents of a key bindings file.
+    ///
+    /// A missing action keeps its default key, and so does an action bound to
+    /// an unknown key (which is reported). A file that isn't valid TOML is an error.
+    fn from_toml(contents: &str) -> Result<Self, toml::de::Error> {
+        let names: KeyNames = toml::from_str(contents)?;
+        let mut keys = Self::default();
+        let actions = [
+            ("up", names.up, &mut keys.up),
+            ("down", names.down, &mut keys.down),
+            ("left", names.left, &mut keys.left),
+            ("right", names.right, &mut keys.right),
+            ("pause", names.pause, &mut keys.pause),
+            ("restart", names.restart, &mut keys.restart),
+            ("leaderboard", names.leaderboard, &mut keys.leaderboard),
+            ("quit", names.quit, &mut keys.quit),
+        ];
+        for (action, name, key) in actions {
+            if let Some(name) = name {
+                match key_from_name(&name) {
+                    Some(code) => *key = code,
+                    None => warn!(
+                        "Unknown key `{}` for `{}`, using the default (`{}`)",
+                        name,
+                        action,
+                        key_name(*key).unwrap_or("?")
+                    ),
+                }
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Loads the key bindings from the given file.
+    ///
+    /// A missing file simply means that nothing was rebound,
+    /// and a corrupt one is reported, so both use the defaults.
+    pub fn load_from(path: &Path) -> Self {
+        match fs::read_to_string(path) {
+            Ok(contents) => Self::from_toml(&contents).unwrap_or_else(|error| {
+                warn!(
+                    "Corrupt key bindings file `{}` ({}), using the defaults",
+                    path.display(),
+                    error
+                );
+                Self::default()
+            }),
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => Self::default(),
+            Err(error) => {
+                warn!(
+                    "Failed to read key bindings file `{}`: {}",
+                    path.display(),
+                    error
+                );
+                Self::default()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_names_map_both_ways() {
+        for (name, code) in KEY_NAMES {
+            assert_eq!(key_from_name(name), Some(*code));
+            assert_eq!(key_name(*code), Some(*name));
+        }
+        assert_eq!(key_from_name("SPACE"), Some(KeyCode::Space));
+        assert_eq!(key_from_name("hyper"), None);
+    }
+
+    #[test]
+    fn only_letters_and_digits_type_initials() {
+        assert_eq!(initial_for_key(KeyCode::A), Some('A'));
+        assert_eq!(initial_for_key(KeyCode::Key7), Some('7'));
+        assert_eq!(initial_for_key(KeyCode::Space), None);
+        assert_eq!(initial_for_key(KeyCode::F1), None);
+    }
+
+    #[test]
+    fn bindings_are_parsed_over_the_defaults() {
+        let keys = KeyBindings::from_toml("up = 'w'\nleft = 'a'\npause = 'p'").unwrap();
+        assert_eq!(keys.direction(KeyCode::W), Some(Direction::Up));
+        assert_eq!(keys.direction(KeyCode::A), Some(Direction::Left));
+        assert_eq!(keys.direction(KeyCode::Up), None);
+        assert_eq!(keys.down, KeyCode::Down);
+        assert_eq!(keys.pause, KeyCode::P);
+        assert_eq!(keys.quit, KeyCode::Escape);
+    }
+
+    #[test]
+    fn leaderboard_can_be_moved_off_a_direction() {
+        assert_eq!(KeyBindings::default().leaderboard, KeyCode::L);
+        let keys = KeyBindings::from_toml("right = 'l'\nleaderboard = 'b'").unwrap();
+        assert_eq!(keys.direction(KeyCode::L), Some(Direction::Right));
+        assert_eq!(keys.leaderboard, KeyCode::B);
+    }
+
+    #[test]
+    fn unknown_keys_fall_back_to_the_defaults() {
+        let keys = KeyBindings::from_toml("up = 'hyper'\ndown = 's'").unwrap();
+        assert_eq!(keys.up, KeyCode::Up);
+        assert_eq!(keys.down, KeyCode::S);
+        assert!(KeyBindings::from_toml("up = [").is_err());
+        assert_eq!(
+            KeyBindings::load_from(Path::new("/nonexistent/keys.toml")),
+            KeyBindings::default()
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use crate::primitives::*;
+use std::{fs, path::Path};
+
+/// A custom board, which is loaded from a text file (see `--level`).
+///
+/// The file is a grid of characters, one line per row of the board:
+/// * `#`: a wall.
+/// * `.`: an empty cell.
+/// * `S`: where the head of the snake starts (exactly one).
+/// * `F`: where the food starts (at most one, the center of the board otherwise).
+///
+/// The snake starts moving in the configured start direction, with
+/// the rest of it behind its head (so there has to be room for it).
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Level {
+    pub width: i16,
+    pub height: i16,
+    pub obstacles: Vec<GridPosition>,
+    /// Where the head of the snake starts.
+    pub start: GridPosition,
+    /// Where the food starts (if it's given).
+    pub food: Option<GridPosition>,
+}
+
+impl Level {
+    /// Parses a level from the contents of a level file.
+    ///
+    /// Trailing empty lines are ignored, but every other line must be
+    /// as long as the first one. The errors point at the line (counting
+    /// from 1) that's wrong.
+    pub fn parse(contents: &str) -> SnakeResult<Self> {
+        let rows: Vec<&str> = contents
+            .trim_end()
+            .lines()
+            .map(|line| line.trim_end_matches('\r'))
+            .collect();
+        let width = match rows.first() {
+            Some(row) if !row.is_empty() => row.chars().count(),
+            _ => return Err(SnakeError::LevelError("The level is empty".to_owned())),
+        };
+        let mut level = Self {
+            width: width as i16,
+            height: rows.len() as i16,
+            obstacles: Vec::new(),
+            start: GridPosition::new(0, 0),
+            food: None,
+        };
+        // the lines of the start and the food (counting from 1), once they're found
+        let mut start_line = None;
+        let mut food_line = None;
+        for (y, row) in rows.iter().enumerate() {
+            let line = y + 1;
+            if row.chars().count() != width {
+                return Err(SnakeError::LevelError(format!(
+                    "Line {}: expected {} cells (like the first line), got {}",
+                    line,
+                    width,
+                    row.chars().count()
+                )));
+            }
+            for (x, cell) in row.chars().enumerate() {
+                let position = GridPosition::new(x as i16, y as i16);
+                match cell {
+                    '#' => level.obstacles.push(position),
+                    '.' => {}
+                    'S' => match start_line {
+                        Some(first) => {
+                            return Err(SnakeError::LevelError(format!(
+                                "Line {}: a second start `S` (the first one is on line {})",
+                                line, first
+                            )))
+                        }
+                        None => {
+                            level.start = position;
+                            start_line = Some(line);
+                        }
+                    },
+                    'F' => match food_line {
+                        Some(first) => {
+                            return Err(SnakeError::LevelError(format!(
+                                "Line {}: a second food `F` (the first one is on line {})",
+                                line, first
+                            )))
+                        }
+                        None => {
+                            level.food = Some(position);
+                            food_line = Some(line);
+                        }
+                    },
+                    cell => {
+                        return Err(SnakeError::LevelError(format!(
+                            "Line {}: unknown cell `{}` (expected `#`, `.`, `S` or `F`)",
+                            line, cell
+                        )))
+                    }
+                }
+            }
+        }
+        if start_line.is_none() {
+            return Err(SnakeError::LevelError(
+                "The level has no start `S`".to_owned(),
+            ));
+        }
+        Ok(level)
+    }
+
+    /// Loads a level from the given file.
+    pub fn load_from(path: &Path) -> SnakeResult<Self> {
+        let contents = fs::read_to_string(path)?;
+        Self::parse(&contents)
+    }
+
+    /// Checks whether a snake of the given length, with its head on the start and
+    /// moving in `direction`, fits behind its head (without any walls or wrapping).
+    pub fn fits_snake(&self, direction: Direction, length: usize) -> bool {
+        let behind = direction.inverse();
+        let mut position = self.start;
+        for _ in 1..length {
+            match GridPosition::try_move(
+                position,
+                behind,
+                (self.width, self.height),
+                EdgeBehavior::Wall,
+            ) {
+                Some(next) if !self.obstacles.contains(&next) => position = next,
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVEL: &str = "\
+######
+#S..F#
+#..#.#
+######
+";
+
+    #[test]
+    fn levels_are_parsed() {
+        let level = Level::parse(LEVEL).unwrap();
+        assert_eq!((level.width, level.height), (6, 4));
+        assert_eq!(level.start, GridPosition::new(1, 1));
+        assert_eq!(level.food, Some(GridPosition::new(4, 1)));
+        assert_eq!(level.obstacles.len(), 6 + 2 + 3 + 6);
+        assert!(level.obstacles.contains(&GridPosition::new(3, 2)));
+        // and the snake only fits where there's room behind it
+        assert!(level.fits_snake(Direction::Left, 3));
+        assert!(!level.fits_snake(Direction::Right, 2));
+        assert!(level.fits_snake(Direction::Up, 2));
+    }
+
+    #[test]
+    fn invalid_levels_point_at_the_line() {
+        let error = |contents: &str| match Level::parse(contents) {
+            Err(SnakeError::LevelError(message)) => message,
+            result => panic!("Expected a level error, got {:?}", result),
+        };
+        assert!(error("......\n.S..\n").starts_with("Line 2:"));
+        assert!(error("S.....\n..x...\n").starts_with("Line 2: unknown cell `x`"));
+        assert!(error("S.....\n......\n.S....\n").starts_with("Line 3: a second start"));
+        assert_eq!(error("....\n....\n"), "The level has no start `S`");
+        assert_eq!(error("\n\n"), "The level is empty");
+        // windows line endings and trailing empty lines are fine
+        assert!(Level::parse("S...\r\n....\r\n\r\n").is_ok());
+    }
+}
@@ -0,0 +1,24 @@
+//! The game logic and entities of snake-rs.
+//!
+//! The game itself lives in `main.rs`, but everything besides the
+//! window and the event handling is here, so that it can also be
+//! used without a window (see `game::Game` and `examples/headless.rs`).
+
+pub mod ai;
+pub mod audio;
+pub mod board;
+pub mod capture;
+pub mod clock;
+pub mod config;
+pub mod entities;
+pub mod game;
+pub mod keys;
+pub mod level;
+pub mod primitives;
+pub mod replay;
+pub mod rumble;
+pub mod save;
+pub mod score;
+pub mod settings;
+pub mod state;
+pub mod theme;
@@ -2,159 +2,1089 @@
 
 use ggez::{
     self,
-    conf::{WindowMode, WindowSetup},
+    conf::{FullscreenType, WindowMode, WindowSetup},
     event::{self, Axis, Button, EventHandler, KeyCode},
     graphics::{
-        self, spritebatch::SpriteBatch, Color, DrawParam, Image, Scale, Text, TextFragment,
-        DEFAULT_FONT_SCALE,
+        self, spritebatch::SpriteBatch, Color, DrawMode, DrawParam, Image, Mesh, MeshBuilder, Rect,
+        Scale, Text, TextFragment, DEFAULT_FONT_SCALE,
     },
     input::{self, gamepad::GamepadId},
-    audio::{Source, SoundSource},
     mint::Point2,
     timer, Context, ContextBuilder, GameResult,
 };
+use log::{info, trace, warn};
+use rand::{thread_rng, Rng};
+use snake_rs::{
+    audio::*, board::*, capture::*, clock::*, config::*, entities::*, game::*, keys::*,
+    primitives::*, replay::*, rumble::*, save::*, score::*, settings::*, state::*, theme::*,
+};
 use std::env;
-use std::path;
+use std::path::{self, Path, PathBuf};
+use std::process;
+use std::thread;
 use std::time::{Duration, Instant};
 
-mod primitives;
-use primitives::*;
-mod entities;
-use entities::*;
+/// The tint of player two's snake (player one's is configurable, see `GameConfig`).
+const PLAYER_TWO_COLOR: Color = Color::new(0.5, 0.7, 1.0, 1.0);
+/// The width (in pixels) of the bars under the score, which show the time left
+/// of a power-up (once it's picked up) and how long until the snake starves.
+const HUD_BAR_WIDTH: f32 = 80.0;
+/// The color of the bar that shows how long until the snake starves.
+const HUNGER_BAR_COLOR: Color = Color::new(1.0, 0.6, 0.2, 1.0);
 
-/// This is the game state struct that will contain
-/// all the needed state (snake, food, game over, ...)
+/// This is the application struct that wraps the `Game` itself with
+/// everything needed to play it in a window (rendering, audio, menus, ...)
 /// and implement the `EventHandler` trait to listen and respond to
 /// events.
-struct Game {
-    game_over: bool,
-    snake: Snake,
-    food: Food,
-    last_update: Instant,
+///
+/// The assets (the spritesheet, the sounds and the background and grid
+/// batches) are loaded once and kept for as long as the `App` lives,
+/// while all of the mutable state of a run is in the `Game`, so
+/// restarting only has to replace the latter (see `App::restart`).
+struct App {
+    config: GameConfig,
+    game: Game,
+    state: GameState,
+    title_text: Text,
+    start_text: Text,
+    paused_text: Text,
+    /// Whether the game was paused automatically because the window lost
+    /// focus (as opposed to by the player), so it resumes once it's regained.
+    auto_paused: bool,
+    /// The game-over (or win) message, which is only
+    /// built once when the game ends.
+    finished_text: Option<Text>,
+    /// When the snake died (used to shake the screen for a moment afterwards).
+    died_at: Option<Instant>,
+    /// When the finished game restarts on its own (see `GameConfig::auto_restart`).
+    restart_at: Option<Instant>,
+    /// When each player's snake last ate (used to pulse its head for a moment afterwards).
+    ate_at: Vec<Option<Instant>>,
+    /// When the game was unpaused, while the countdown before
+    /// it actually resumes is still going.
+    resume_countdown: Option<Instant>,
+    score_text: Text,
+    /// The combos that `score_text` shows (so that it's only
+    /// updated once one of them changes).
+    combos: Vec<u32>,
+    length_text: Text,
+    /// The lengths of the snakes that `length_text` shows
+    /// (so that it's only updated once one of them changes).
+    lengths: Vec<usize>,
+    high_score: HighScore,
+    scoreboard: Scoreboard,
+    /// The initials typed so far, for a score that made it onto the `scoreboard`.
+    initials: String,
+    leaderboard_text: Text,
+    /// When the last frame was updated.
+    last_frame: Instant,
+    /// Caps the frame rate (`None` if it's uncapped, see `GameConfig::fps`).
+    frame_limiter: Option<FrameLimiter>,
+    /// Dumps the rendered frames (if enabled, see `GameConfig::record_frames`).
+    frame_recorder: Option<FrameRecorder>,
+    /// The time that the game logic didn't catch up with yet.
+    clock: UpdateClock,
     sprites: Image,
-    eat_sound: Source,
-    die_sound: Source,
+    sounds: Sounds,
+    rumble: Rumble,
+    /// The master volume of the sound effects (between 0.0 and 1.0).
+    volume: f32,
+    muted_text: Text,
+    /// Whether the window is currently fullscreen.
+    fullscreen: bool,
+    settings: Settings,
+    settings_path: PathBuf,
+    /// The cursor of the settings menu (which keeps its place between openings).
+    settings_menu: SettingsMenu,
+    save_path: PathBuf,
+    /// The keys that the player's actions are bound to.
+    keys: KeyBindings,
+    /// The recording that's being replayed (instead of the player playing).
+    replay: Option<Recording>,
+    /// Where the last run is recorded to, once it's over.
+    replay_path: PathBuf,
     background: SpriteBatch,
+    /// The lines between the cells of the board (for debugging alignment).
+    grid: Mesh,
+    show_grid: bool,
+    /// Whether the current tick rate is shown (for debugging).
+    show_tick_rate: bool,
+    /// Whether the snakes are drawn at their cells without sliding towards the next
+    /// update, with markers on the cells that they're on in the game (for debugging).
+    show_logical_cells: bool,
+    /// Whether an arrow shows where a snake that's about to wrap around
+    /// the edge of the board emerges (see `Board::wrap_exit`).
+    show_wrap_indicator: bool,
+    /// Whether the background is a checkerboard (which starts as configured).
+    checkerboard: bool,
+    /// Whether the snakes fade towards their tails (which starts as configured).
+    gradient: bool,
+    /// The active theme (which starts as the configured one).
+    theme: Theme,
 }
 
-impl Game {
-    /// Helper function to create a new `Game`.
-    fn new(ctx: &mut Context) -> GameResult<Self> {
-        // load the spritesheet
+impl App {
+    /// Helper function to create a new `App` with the given configuration.
+    fn new(
+        ctx: &mut Context,
+        mut config: GameConfig,
+        replay: Option<Recording>,
+    ) -> GameResult<Self> {
+        // load the player's settings, where the theme and the difficulty picked in
+        // the settings menu replace the defaults (but not the command line)
+        let settings_path = Settings::path(ctx);
+        let settings = Settings::load_from(&settings_path);
+        if !config.theme_given {
+            if let Some(theme) = settings.theme.as_deref().and_then(|name| name.parse().ok()) {
+                config.theme = theme;
+            }
+        }
+        if !config.difficulty_given {
+            if let Some(difficulty) = settings
+                .difficulty
+                .as_deref()
+                .and_then(|name| name.parse().ok())
+            {
+                config.difficulty = difficulty;
+            }
+        }
+
+        let game = Game::new(&config);
+        let board = game.board();
+
+        // load the spritesheet (scaled with the player's preferred filtering)
+        graphics::set_default_filter(ctx, settings.filter_mode());
         let sprites = Image::new(ctx, "/sprites.png")?;
 
-        // load the audio
-        let eat_sound = Source::new(ctx, "/eat-sound.ogg")?;
-        let mut die_sound = Source::new(ctx, "/die-sound.ogg")?;
-        die_sound.set_volume(0.5);
+        // load the audio (missing sounds are skipped rather than failing)
+        let mut sounds = Sounds::load(ctx);
+        sounds.enabled = settings.audio_enabled;
 
-        // generate the background spritebatch
-        let mut background = SpriteBatch::new(sprites.clone());
-        for x in 0..GRID_SIZE.0 {
-            for y in 0..GRID_SIZE.1 {
-                background.add(&PositionedSprite::new(
-                    Sprite::Grass,
-                    GridPosition::new(x, y),
-                ));
-            }
+        let background = Self::background(
+            &sprites,
+            board,
+            config.cell_size,
+            &config.theme,
+            config.checkerboard,
+            config.border,
+        );
+
+        // generate the grid lines mesh (which never changes either)
+        let mut grid = MeshBuilder::new();
+        let line_color = Color::new(0.0, 0.0, 0.0, 0.4);
+        for x in 0..=board.width {
+            let top = GridPosition::new(x, 0).to_point(config.cell_size);
+            let bottom = GridPosition::new(x, board.height).to_point(config.cell_size);
+            grid.line(&[top, bottom], 1.0, line_color)?;
+        }
+        for y in 0..=board.height {
+            let left = GridPosition::new(0, y).to_point(config.cell_size);
+            let right = GridPosition::new(board.width, y).to_point(config.cell_size);
+            grid.line(&[left, right], 1.0, line_color)?;
         }
+        let grid = grid.build(ctx)?;
 
+        let lengths: Vec<usize> = game.snakes().iter().map(Snake::len).collect();
+        let scoreboard = Scoreboard::load(ctx);
         Ok(Self {
-            snake: Snake::new((1, 0).into()),
-            food: Food::new((GRID_SIZE.0 / 2, GRID_SIZE.1 / 2).into()),
-            game_over: false,
-            last_update: Instant::now(),
+            state: GameState::Menu,
+            title_text: Text::new(
+                TextFragment::new("SNAKE").scale(Scale::uniform(DEFAULT_FONT_SCALE * 6.0)),
+            ),
+            start_text: Self::start_text(config.difficulty),
+            paused_text: Text::new(
+                TextFragment::new("PAUSED").scale(Scale::uniform(DEFAULT_FONT_SCALE * 2.0)),
+            ),
+            auto_paused: false,
+            finished_text: None,
+            died_at: None,
+            restart_at: None,
+            ate_at: vec![None; game.snakes().len()],
+            resume_countdown: None,
+            score_text: Self::score_text(game.scores(), game.combos()),
+            combos: game.combos().to_vec(),
+            length_text: Self::length_text(&lengths),
+            lengths,
+            high_score: HighScore::load(ctx),
+            leaderboard_text: Self::leaderboard_text(&scoreboard),
+            scoreboard,
+            initials: String::new(),
+            last_frame: Instant::now(),
+            frame_limiter: if config.fps > 0 {
+                Some(FrameLimiter::new(config.fps))
+            } else {
+                None
+            },
+            frame_recorder: config.record_frames.as_deref().map(FrameRecorder::new),
+            clock: UpdateClock::new(),
             sprites,
-            eat_sound,
-            die_sound,
+            sounds,
+            rumble: Rumble::new(),
+            volume: 1.0,
+            muted_text: Text::new("MUTED"),
+            fullscreen: false,
+            show_grid: settings.show_grid,
+            settings,
+            settings_path,
+            settings_menu: SettingsMenu::default(),
+            save_path: SavedGame::path(ctx),
+            keys: KeyBindings::load_from(&KeyBindings::path(ctx)),
+            replay,
+            replay_path: Recording::path(ctx),
             background,
+            grid,
+            show_tick_rate: false,
+            show_logical_cells: false,
+            show_wrap_indicator: true,
+            checkerboard: config.checkerboard,
+            gradient: config.gradient,
+            theme: config.theme,
+            game,
+            config,
         })
     }
 
-    /// Helper function that generates a new random
-    /// position for the food while ensuring that it
-    /// doesn't collide with the snake.
+    /// Helper function that generates the background spritebatch for the board,
+    /// in the colors of the given theme (as a `checkerboard`, if enabled).
+    ///
+    /// The obstacles (and the `border` around the board, if enabled) never
+    /// move, so they're a part of the background as well.
+    fn background(
+        sprites: &Image,
+        board: &Board,
+        cell_size: (i16, i16),
+        theme: &Theme,
+        checkerboard: bool,
+        border: bool,
+    ) -> SpriteBatch {
+        let mut background = SpriteBatch::new(sprites.clone());
+        for position in board.cells() {
+            background.add(
+                PositionedSprite::new(Sprite::Grass, position)
+                    .to_draw_param(cell_size)
+                    .color(theme.grass_at(position, checkerboard)),
+            );
+        }
+        for obstacle in &board.obstacles {
+            background.add(PositionedSprite::new(Sprite::Wall, *obstacle).to_draw_param(cell_size));
+        }
+        if border {
+            for position in board.border_cells() {
+                background
+                    .add(PositionedSprite::new(Sprite::Wall, position).to_draw_param(cell_size));
+            }
+        }
+        background
+    }
+
+    /// Helper function that switches to the next theme.
+    fn cycle_theme(&mut self) {
+        self.theme = self.theme.next();
+        self.rebuild_background();
+    }
+
+    /// Helper function that switches the checkerboard background on or off.
+    fn toggle_checkerboard(&mut self) {
+        self.checkerboard = !self.checkerboard;
+        self.rebuild_background();
+    }
+
+    /// Helper function that regenerates the background spritebatch
+    /// after the way it's drawn changed (it's only built once otherwise).
+    fn rebuild_background(&mut self) {
+        self.background = Self::background(
+            &self.sprites,
+            self.game.board(),
+            self.config.cell_size,
+            &self.theme,
+            self.checkerboard,
+            self.config.border,
+        );
+    }
+
+    /// Helper function that creates the HUD `Text` for the given scores
+    /// and combos (of each of the players).
+    fn score_text(scores: &[u32], combos: &[u32]) -> Text {
+        // only show the combos that are actually worth something
+        let combo = |combo: u32| match combo_multiplier(combo) {
+            1 => String::new(),
+            multiplier => format!(" (x{})", multiplier),
+        };
+        match (scores, combos) {
+            ([score], [player_combo]) => {
+                Text::new(format!("Score: {}{}", score, combo(*player_combo)))
+            }
+            (scores, combos) => Text::new(
+                scores
+                    .iter()
+                    .zip(combos)
+                    .enumerate()
+                    .map(|(index, (score, player_combo))| {
+                        format!("P{}: {}{}", index + 1, score, combo(*player_combo))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("   "),
+            ),
+        }
+    }
+
+    /// Helper function that creates the HUD `Text` for the given
+    /// lengths (of the snakes of each of the players).
+    fn length_text(lengths: &[usize]) -> Text {
+        match lengths {
+            [length] => Text::new(format!("Len: {}", length)),
+            lengths => Text::new(
+                lengths
+                    .iter()
+                    .enumerate()
+                    .map(|(index, length)| format!("P{} len: {}", index + 1, length))
+                    .collect::<Vec<_>>()
+                    .join("   "),
+            ),
+        }
+    }
+
+    /// Helper function that creates the start menu `Text` for the given difficulty.
+    fn start_text(difficulty: Difficulty) -> Text {
+        Text::new(
+            TextFragment::new(format!(
+                "Press ENTER or START to start\nPress L for the leaderboard\nDifficulty: {}",
+                difficulty.name()
+            ))
+            .scale(Scale::uniform(DEFAULT_FONT_SCALE * 2.0)),
+        )
+    }
+
+    /// Helper function that creates the settings menu `Text`, with the current
+    /// value of every option and a cursor next to the selected one.
+    fn settings_text(&self) -> Text {
+        let on_off = |enabled: bool| if enabled { "on" } else { "off" };
+        let options: Vec<String> = SettingsOption::ALL
+            .iter()
+            .map(|option| {
+                let value = match option {
+                    SettingsOption::Sound => on_off(self.sounds.enabled),
+                    SettingsOption::Grid => on_off(self.show_grid),
+                    SettingsOption::Theme => self.theme.name,
+                    SettingsOption::Difficulty => self.config.difficulty.name(),
+                };
+                let cursor = if *option == self.settings_menu.selected() {
+                    ">"
+                } else {
+                    "  "
+                };
+                format!("{} {}: {}", cursor, option.label(), value)
+            })
+            .collect();
+        Text::new(
+            TextFragment::new(format!(
+                "SETTINGS\n\n{}\n\nUP/DOWN to choose, LEFT/RIGHT to change\nO to close",
+                options.join("\n")
+            ))
+            .scale(Scale::uniform(DEFAULT_FONT_SCALE * 2.0)),
+        )
+    }
+
+    /// Helper function that creates the leaderboard `Text` for the given scoreboard.
+    fn leaderboard_text(scoreboard: &Scoreboard) -> Text {
+        let entries = if scoreboard.entries().is_empty() {
+            "No scores yet!".to_owned()
+        } else {
+            scoreboard
+                .entries()
+                .iter()
+                .enumerate()
+                .map(|(index, (initials, score))| {
+                    format!("{:>2}. {}  {}", index + 1, initials, score)
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        Text::new(
+            TextFragment::new(format!(
+                "LEADERBOARD\n\n{}\n\nPress ENTER or START to play",
+                entries
+            ))
+            .scale(Scale::uniform(DEFAULT_FONT_SCALE * 2.0)),
+        )
+    }
+
+    /// Helper function that updates the cached length `Text`
+    /// if the length of any of the snakes changed.
+    fn update_length_text(&mut self) {
+        let lengths: Vec<usize> = self.game.snakes().iter().map(Snake::len).collect();
+        if lengths != self.lengths {
+            self.length_text = Self::length_text(&lengths);
+            self.lengths = lengths;
+        }
+    }
+
+    /// Helper function that updates the cached score `Text`
+    /// after the score (or a combo) of the `Game` changed.
+    fn update_score_text(&mut self) {
+        self.combos = self.game.combos().to_vec();
+        self.score_text = Self::score_text(self.game.scores(), &self.combos);
+    }
+
+    /// Helper function that ends the game with the given (final) result,
+    /// recording the final score.
+    fn finish(&mut self, result: StepResult) {
+        let won = result == StepResult::Won;
+        self.state = self.state.finish(won);
+        self.restart_at = self.config.auto_restart.map(|delay| Instant::now() + delay);
+        if !won && result != StepResult::TimeUp {
+            self.died_at = Some(Instant::now());
+        }
+
+        let message = if self.game.is_two_players() {
+            // the high score is only kept for the single-player game
+            let scores = self.game.scores();
+            let headline = match result {
+                StepResult::PlayerLost(Player::One) => "Player 2 wins!",
+                StepResult::PlayerLost(Player::Two) => "Player 1 wins!",
+                StepResult::Won => "The board is full!",
+                // when the time is up, the higher score wins
+                StepResult::TimeUp if scores[0] > scores[1] => "Time's up! Player 1 wins!",
+                StepResult::TimeUp if scores[0] < scores[1] => "Time's up! Player 2 wins!",
+                StepResult::TimeUp => "Time's up! It's a draw!",
+                _ => "It's a draw!",
+            };
+            format!(
+                "{} \nFinal scores: {} - {}\nPress ENTER or START to play again",
+                headline, scores[0], scores[1]
+            )
+        } else {
+            let score = self.game.score();
+            self.high_score.submit(score);
+            // a replayed run already made it onto the scoreboard (if it could)
+            if self.replay.is_none() && self.scoreboard.qualifies(score) {
+                self.state = self.state.enter_initials();
+                self.initials.clear();
+            }
+            format!(
+                "{} \nFinal score: {}\nHigh score: {}\n{}",
+                match result {
+                    StepResult::Won => "YOU WIN!",
+                    StepResult::TimeUp => "Time's up!",
+                    _ => "Game Over!",
+                },
+                score,
+                self.high_score.best(),
+                if self.state == GameState::EnteringInitials {
+                    "A new top 10 score! Type your initials and press ENTER"
+                } else {
+                    "Press ENTER or START to play again"
+                }
+            )
+        };
+        let fragment = TextFragment::new(message).scale(Scale::uniform(DEFAULT_FONT_SCALE * 2.0));
+        self.finished_text = Some(Text::new(fragment));
+
+        // record the run, so that it can be replayed (but not a replay itself)
+        if let (None, Some(recording)) = (&self.replay, self.game.recording()) {
+            if let Err(error) = recording.save_to(&self.replay_path) {
+                warn!(
+                    "Failed to save the replay to `{}`: {}",
+                    self.replay_path.display(),
+                    error
+                );
+            }
+        }
+    }
+
+    /// Helper function that types the given key into the initials of the new
+    /// scoreboard entry, which is inserted once all of them were typed.
+    fn type_initial(&mut self, keycode: KeyCode) {
+        if keycode == KeyCode::Back {
+            self.initials.pop();
+        } else if keycode == self.keys.restart && self.initials.len() == INITIALS_LENGTH {
+            self.scoreboard.insert(&self.initials, self.game.score());
+            self.leaderboard_text = Self::leaderboard_text(&self.scoreboard);
+            self.state = self.state.toggle_leaderboard();
+        } else if let Some(initial) = initial_for_key(keycode) {
+            if self.initials.len() < INITIALS_LENGTH {
+                self.initials.push(initial);
+            }
+        }
+    }
+
+    /// Helper function that opens / closes the leaderboard.
+    fn toggle_leaderboard(&mut self) {
+        self.state = self.state.toggle_leaderboard();
+        // the leaderboard closes back to the menu, which needs a fresh game
+        // (without the start sound, since the game doesn't start yet)
+        if self.state == GameState::Menu {
+            self.reset_game();
+        }
+    }
+
+    /// Helper function that advances the `Game` by a single tick, and
+    /// responds to what happened in it (e.g. with sounds, or by ending the game).
+    fn step(&mut self) {
+        if let Some(replay) = &self.replay {
+            replay.replay_into(&mut self.game);
+        }
+        let scores = self.game.scores().to_vec();
+        let result = self.game.step_logic();
+        // pulse the head of every snake that scored (i.e. ate food)
+        for (ate_at, (before, after)) in self
+            .ate_at
+            .iter_mut()
+            .zip(scores.iter().zip(self.game.scores()))
+        {
+            if after > before {
+                *ate_at = Some(Instant::now());
+            }
+        }
+        // the snakes can grow even without eating (see `Snake::grow`)
+        self.update_length_text();
+        // audio (and rumble) is best effort, so we don't care if it fails
+        let combo = self.game.combos().iter().copied().max().unwrap_or(0);
+        self.sounds
+            .play_for(result, eat_pitch(&mut thread_rng(), combo));
+        self.rumble.play_for(result);
+        match result {
+            // a combo running out changes the HUD as well
+            StepResult::Moved => {
+                if self.game.combos() != self.combos.as_slice() {
+                    self.update_score_text();
+                }
+            }
+            StepResult::Ate(_) => self.update_score_text(),
+            // dying in practice only shakes the screen (and resets the combo)
+            StepResult::Respawned(_) => {
+                self.died_at = Some(Instant::now());
+                self.update_score_text();
+            }
+            // anything else ends the game
+            result => {
+                self.update_score_text();
+                self.finish(result);
+            }
+        }
+    }
+
+    /// Helper function that checks whether the given key steers a snake.
+    fn is_direction_key(&self, keycode: KeyCode) -> bool {
+        self.keys.direction(keycode).is_some() || Direction::from_wasd_keycode(keycode).is_some()
+    }
+
+    /// Helper function that steers the given player's snake (which
+    /// the player can't do while a recording is being replayed).
+    fn steer(&mut self, player: Player, direction: Direction) {
+        if self.replay.is_none() {
+            let previous_direction = self.game.snakes()[player.index()].next_direction();
+            // this method may fail if the direction is not orthogonal,
+            // but we don't especially care ;)
+            let _ = self.game.set_player_direction(player, direction);
+            if self.game.snakes()[player.index()].next_direction() != previous_direction {
+                self.sounds.play_turn();
+            }
+        }
+    }
+
+    /// Helper function that returns how much to offset the scene by,
+    /// to shake the screen shortly after the snake died.
     ///
-    /// note: this algorithm ***isn't*** good.
-    fn generate_food_position(&self) -> GridPosition {
-        let segments = self.snake.segments();
-        let mut position = GridPosition::random(GRID_SIZE.0, GRID_SIZE.1);
-        while segments.contains(&position) {
-            position = GridPosition::random(GRID_SIZE.0, GRID_SIZE.1);
+    /// The shake is random (but doesn't use the seeded `rng`, so that it
+    /// doesn't affect the game) and decays linearly until it stops.
+    fn shake_offset(&self) -> Point2<f32> {
+        let duration = Duration::from_millis(DEATH_SHAKE_MILLIS);
+        match self.died_at.map(|died_at| died_at.elapsed()) {
+            Some(elapsed) if elapsed < duration => {
+                let magnitude =
+                    DEATH_SHAKE_MAGNITUDE * (1.0 - elapsed.as_secs_f32() / duration.as_secs_f32());
+                let mut rng = thread_rng();
+                Point2 {
+                    x: rng.gen_range(-magnitude, magnitude),
+                    y: rng.gen_range(-magnitude, magnitude),
+                }
+            }
+            _ => Point2 { x: 0.0, y: 0.0 },
+        }
+    }
+
+    /// Helper function that starts the game from the menu.
+    fn start(&mut self) {
+        self.state = self.state.start();
+        self.clock.reset();
+        self.sounds.play_start();
+    }
+
+    /// Helper function that pauses or unpauses the game.
+    fn toggle_pause(&mut self) {
+        self.auto_paused = false;
+        // the time spent paused never reaches the clock (see `update`),
+        // so the snake carries on from exactly where it was, after
+        // a countdown that gives the player a moment to get ready
+        self.state = self.state.toggle_pause();
+        self.resume_countdown = if self.state == GameState::Playing {
+            Some(Instant::now())
+        } else {
+            None
+        };
+    }
+
+    /// Helper function that mutes / unmutes the sound effects,
+    /// remembering the choice for the next runs.
+    fn toggle_audio(&mut self) {
+        self.settings.audio_enabled = !self.settings.audio_enabled;
+        self.sounds.enabled = self.settings.audio_enabled;
+        self.settings.save_to(&self.settings_path);
+    }
+
+    /// Helper function that switches between crisp and smooth scaling of the
+    /// sprites, remembering the choice for the next runs.
+    fn toggle_filtering(&mut self) {
+        self.settings.smooth_filtering = !self.settings.smooth_filtering;
+        let mode = self.settings.filter_mode();
+        self.sprites.set_filter(mode);
+        self.background.set_filter(mode);
+        self.settings.save_to(&self.settings_path);
+    }
+
+    /// Helper function that opens / closes the settings menu,
+    /// remembering the choices for the next runs once it closes.
+    fn toggle_settings(&mut self) {
+        self.state = self.state.toggle_settings();
+        // the countdown only starts once the game is unpaused again
+        self.resume_countdown = None;
+        if self.state == GameState::Paused {
+            self.settings.show_grid = self.show_grid;
+            self.settings.theme = Some(self.theme.name.to_owned());
+            self.settings.difficulty = Some(self.config.difficulty.name().to_owned());
+            self.settings.save_to(&self.settings_path);
         }
-        position
     }
 
-    /// Helper function that restarts reinitializes
-    /// the `Game` to its starting state.
+    /// Helper function that changes the option selected in the settings
+    /// menu to its next (or, if not `forward`, previous) value, live.
+    fn change_setting(&mut self, forward: bool) {
+        match self.settings_menu.selected() {
+            SettingsOption::Sound => self.toggle_audio(),
+            SettingsOption::Grid => self.show_grid = !self.show_grid,
+            SettingsOption::Theme => {
+                self.theme = if forward {
+                    self.theme.next()
+                } else {
+                    self.theme.previous()
+                };
+                self.rebuild_background();
+            }
+            SettingsOption::Difficulty => {
+                let difficulty = if forward {
+                    self.config.difficulty.harder()
+                } else {
+                    self.config.difficulty.easier()
+                };
+                // the restarted games keep the difficulty as well
+                self.config.difficulty = difficulty;
+                self.game.set_difficulty(difficulty);
+                self.start_text = Self::start_text(difficulty);
+            }
+        }
+    }
+
+    /// Helper function that raises / lowers the master volume by `delta`.
+    fn change_volume(&mut self, delta: f32) {
+        self.volume = (self.volume + delta).clamp(0.0, 1.0);
+        self.sounds.set_volume(self.volume);
+    }
+
+    /// Helper function that switches the window between fullscreen and windowed.
     ///
-    /// note: there is a probably a better and more
-    /// efficient way to do this.
-    fn restart(&mut self, ctx: &mut Context) {
-        let game = Game::new(ctx).expect("Failed to restart the game");
-        self.snake = game.snake;
-        self.food = game.food;
-        self.game_over = game.game_over;
-        self.last_update = game.last_update;
+    /// The grid itself is letterboxed in `resize_event`.
+    fn toggle_fullscreen(&mut self, ctx: &mut Context) {
+        let fullscreen_type = if self.fullscreen {
+            FullscreenType::Windowed
+        } else {
+            FullscreenType::Desktop
+        };
+        match graphics::set_fullscreen(ctx, fullscreen_type) {
+            Ok(()) => self.fullscreen = !self.fullscreen,
+            Err(error) => warn!("Failed to toggle fullscreen: {}", error),
+        }
+    }
+
+    /// Saves the current run to the given file, so that it can be resumed later.
+    ///
+    /// Only a run that's still going (playing or paused) can be saved,
+    /// and not during a replay (see `check_can_save`).
+    fn save(&self, path: &Path) -> SnakeResult {
+        check_can_save(self.state, self.replay.is_some())?;
+        self.game.snapshot().save_to(path)
+    }
+
+    /// Resumes the run saved in the given file (paused, so
+    /// that the player has a moment to get ready).
+    ///
+    /// A saved run can't be loaded during a replay (see `check_can_load`).
+    fn load(&mut self, path: &Path) -> SnakeResult {
+        check_can_load(self.replay.is_some())?;
+        self.game.restore(SavedGame::load_from(path)?)?;
+        self.update_score_text();
+        self.update_length_text();
+        self.state = GameState::Paused;
+        self.clock.reset();
+        self.auto_paused = false;
+        self.resume_countdown = None;
+        self.finished_text = None;
+        self.died_at = None;
+        self.restart_at = None;
+        self.ate_at = vec![None; self.game.snakes().len()];
+        Ok(())
+    }
+
+    /// Helper function that restarts the `Game` from its starting state.
+    fn restart(&mut self) {
+        self.reset_game();
+        // we've already seen the menu, so go straight back to playing
+        self.state = GameState::Playing;
+        self.sounds.play_start();
+    }
+
+    /// Helper function that replaces the `Game` with a fresh one (and clears
+    /// everything left over from the last one), without changing the state.
+    fn reset_game(&mut self) {
+        self.game = Game::new(&self.config);
+        self.auto_paused = false;
+        self.resume_countdown = None;
+        self.finished_text = None;
+        self.died_at = None;
+        self.restart_at = None;
+        self.ate_at = vec![None; self.game.snakes().len()];
+        self.update_score_text();
+        self.update_length_text();
+        self.clock.reset();
     }
 }
 
-impl EventHandler for Game {
+impl EventHandler for App {
     fn update(&mut self, _ctx: &mut Context) -> GameResult {
-        // we want to update only if the game is not over and enough time
-        // has passed since the last update
-        if !self.game_over
-            && Instant::now() - self.last_update >= Duration::from_millis(MILLIS_PER_UPDATE)
-        {
-            if let Some(ate) = self.snake.update(&self.food) {
-                match ate {
-                    // game over if the snake ate itself
-                    Ate::Itself => {
-                        self.die_sound.play()?;
-                        self.game_over = true;
-                    },
-                    // if the snake ate the food, we need to change its position
-                    // note: we need to add a way grill a random position *without*
-                    // a snake segment.
-                    Ate::Food => {
-                        self.eat_sound.play()?;
-                        self.food.set_position(self.generate_food_position())
-                    },
-                }
+        // we only want to update while playing, so the time of any other
+        // frame (e.g. while paused) is just skipped
+        let now = Instant::now();
+        let elapsed = now - self.last_frame;
+        self.last_frame = now;
+        self.rumble.poll();
+        // run a single tick while paused, if one was requested (for debugging)
+        if self.state == GameState::Paused && self.game.take_step_request() {
+            // only a running game can finish, so it runs for just this tick
+            self.state = GameState::Playing;
+            self.step();
+            if self.state == GameState::Playing {
+                self.state = GameState::Paused;
             }
-            // update the last update time
-            self.last_update = Instant::now();
+        }
+        // restart a finished game on its own once it's time to (from wherever
+        // it could be restarted by hand, so e.g. typing initials isn't cut off)
+        let restartable = self.state.is_finished() || self.state == GameState::Leaderboard;
+        if restartable && self.restart_at.is_some_and(|restart_at| now >= restart_at) {
+            self.restart();
+        }
+        if self.state != GameState::Playing {
+            return Ok(());
+        }
+        // the snake waits for the countdown to end after unpausing
+        if let Some(unpaused_at) = self.resume_countdown {
+            if resume_countdown_number(unpaused_at.elapsed()).is_some() {
+                return Ok(());
+            }
+            self.resume_countdown = None;
+        }
+
+        // run as many updates as are due since the last frame (their
+        // interval gets shorter as the snake grows), no matter the frame rate
+        self.clock.advance(elapsed, self.game.update_interval());
+        while self.state == GameState::Playing && self.clock.tick(self.game.update_interval()) {
+            trace!("update (interval: {:?})", self.game.update_interval());
+            self.step();
         }
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        graphics::clear(ctx, Color::from_rgb(40, 50, 130));
-
-        // draw the game in the following order: background -> snake -> food
-        graphics::draw(ctx, &mut self.background, DrawParam::default())?;
-        self.snake.draw(ctx, &mut self.sprites)?;
-        self.food.draw(ctx, &mut self.sprites)?;
-
-        // show the game-over screen
-        // TODO: I'm pretty sure that creating a new `TextFragment` each time is
-        // a horrible horrible thing to do....
-        if self.game_over {
-            let fragment = TextFragment::new("Game Over! \nPress ENTER or START to play again")
-                .scale(Scale::uniform(DEFAULT_FONT_SCALE * 2.0));
-            let text = &mut Text::new(fragment);
-            let dimensions = text.dimensions(ctx);
+        graphics::clear(ctx, self.theme.background);
+
+        // shake the scene (but not the HUD) right after dying, and move it
+        // inside of the border (which is drawn just outside of the board)
+        let shake = self.shake_offset();
+        let offset = self.config.board_offset();
+        let shake = DrawParam::default().dest(Point2 {
+            x: shake.x + offset.0,
+            y: shake.y + offset.1,
+        });
+        graphics::push_transform(ctx, Some(shake.to_matrix()));
+        graphics::apply_transformations(ctx)?;
+
+        // draw the game in the following order: background (and obstacles) -> portals -> snake -> food
+        graphics::draw(ctx, &self.background, DrawParam::default())?;
+        let cell_size = self.config.cell_size;
+        for &(entrance, exit) in self.game.portals() {
+            for position in &[entrance, exit] {
+                let param =
+                    PositionedSprite::new(Sprite::Portal, *position).to_draw_param(cell_size);
+                graphics::draw(ctx, &self.sprites, param)?;
+            }
+        }
+        let colors = [
+            self.config.snake_color.unwrap_or(self.theme.snake),
+            PLAYER_TWO_COLOR,
+        ];
+        // slide the snakes towards their next update (which stays put while
+        // paused), but not once the game is over
+        let update_segment = match self.state {
+            _ if self.show_logical_cells => 0.0,
+            GameState::Playing | GameState::Paused => self
+                .config
+                .easing
+                .apply(self.clock.segment(self.game.update_interval())),
+            _ => 0.0,
+        };
+        if self.config.trail {
+            for (snake, color) in self.game.snakes().iter().zip(&colors) {
+                snake.draw_trail(ctx, &mut self.sprites, cell_size, *color)?;
+            }
+        }
+        for ((snake, color), ate_at) in self.game.snakes().iter().zip(&colors).zip(&self.ate_at) {
+            let head_scale = ate_at.map_or(1.0, |ate_at| eat_pulse_scale(ate_at.elapsed()));
+            // a shielded snake flashes in the color of the shield
+            let color = if snake.is_shielded() && self.game.tick().is_multiple_of(2) {
+                PowerUp::Shield.color()
+            } else {
+                *color
+            };
+            let gradient = if self.gradient {
+                self.theme.snake_gradient(color)
+            } else {
+                Gradient::solid(color)
+            };
+            snake.draw(
+                ctx,
+                &mut self.sprites,
+                cell_size,
+                gradient,
+                update_segment,
+                head_scale,
+            )?;
+        }
+        let food_color = self.theme.food;
+        for food in self.game.foods() {
+            food.draw(ctx, &mut self.sprites, cell_size, food_color)?;
+        }
+        if let Some(bonus_food) = self.game.bonus_food() {
+            bonus_food.draw(ctx, &mut self.sprites, cell_size, food_color)?;
+        }
+        if let Some(poison_food) = self.game.poison_food() {
+            poison_food.draw(ctx, &mut self.sprites, cell_size, food_color)?;
+        }
+        if let Some(power_up) = self.game.power_up_item() {
+            power_up.draw(ctx, &mut self.sprites, cell_size)?;
+        }
+        if self.show_logical_cells {
+            let mut markers = MeshBuilder::new();
+            let marker_color = Color::new(1.0, 1.0, 1.0, 0.4);
+            for cell in self.game.snakes().iter().flat_map(Snake::occupied_cells) {
+                let corner = cell.to_point(cell_size);
+                let bounds = Rect::new(corner.x, corner.y, cell_size.0 as f32, cell_size.1 as f32);
+                markers.rectangle(DrawMode::stroke(2.0), bounds, marker_color);
+            }
+            let markers = markers.build(ctx)?;
+            graphics::draw(ctx, &markers, DrawParam::default())?;
+        }
+        if self.show_wrap_indicator {
+            for (snake, color) in self.game.snakes().iter().zip(&colors) {
+                let direction = snake.next_direction();
+                if let Some(exit) = self
+                    .game
+                    .board()
+                    .wrap_exit(snake.head_position(), direction)
+                {
+                    let arrow = Mesh::new_polygon(
+                        ctx,
+                        DrawMode::fill(),
+                        &exit.arrow_points(direction, cell_size),
+                        *color,
+                    )?;
+                    graphics::draw(ctx, &arrow, DrawParam::default())?;
+                }
+            }
+        }
+        if self.show_grid {
+            graphics::draw(ctx, &self.grid, DrawParam::default())?;
+        }
+
+        graphics::pop_transform(ctx);
+        graphics::apply_transformations(ctx)?;
+
+        // show the score in the top-left corner
+        graphics::draw(ctx, &self.score_text, (Point2 { x: 8.0, y: 8.0 },))?;
+
+        // and the time left of the power-up in effect and the hunger as shrinking bars under it
+        let bars = self
+            .game
+            .active_power_up()
+            .map(|active| (active.fraction_left(), active.power_up.color()))
+            .into_iter()
+            .chain(
+                self.game
+                    .hunger_left(Player::One)
+                    .map(|hunger| (hunger, HUNGER_BAR_COLOR)),
+            );
+        let mut y = 8.0 + self.score_text.height(ctx) as f32 + 4.0;
+        for (fraction, color) in bars {
+            let bar = Mesh::new_rectangle(
+                ctx,
+                DrawMode::fill(),
+                Rect::new(8.0, y, HUD_BAR_WIDTH * fraction, 6.0),
+                color,
+            )?;
+            graphics::draw(ctx, &bar, DrawParam::default())?;
+            y += 10.0;
+        }
+
+        // show the length of the snake in the bottom-left corner
+        let screen_size = self.config.screen_size();
+        let length_height = self.length_text.height(ctx);
+        graphics::draw(
+            ctx,
+            &self.length_text,
+            (Point2 {
+                x: 8.0,
+                y: screen_size.1 - length_height as f32 - 8.0,
+            },),
+        )?;
+
+        // show the time left (of a time attack game) in the top center
+        if let Some(time_left) = self.game.time_left() {
+            // round up, so that the timer only shows 0:00 once the time is up
+            let seconds = (time_left.as_millis() as u64).div_ceil(1000);
+            let text = Text::new(format!("{}:{:02}", seconds / 60, seconds % 60));
+            let width = text.width(ctx);
+            graphics::draw(
+                ctx,
+                &text,
+                (Point2 {
+                    x: screen_size.0 * 0.5 - width as f32 * 0.5,
+                    y: 8.0,
+                },),
+            )?;
+        }
+
+        // show the deaths (of a practice game) in the top center, too
+        if self.game.mode() == GameMode::Practice {
+            let deaths: Vec<String> = self.game.deaths().iter().map(u32::to_string).collect();
+            let text = Text::new(format!("Deaths: {}", deaths.join(" - ")));
+            let width = text.width(ctx);
             graphics::draw(
                 ctx,
-                text,
+                &text,
                 (Point2 {
-                    x: SCREEN_SIZE.0 as f32 * 0.5 - dimensions.0 as f32 * 0.5,
-                    y: SCREEN_SIZE.1 as f32 * 0.5 - dimensions.1 as f32,
+                    x: screen_size.0 * 0.5 - width as f32 * 0.5,
+                    y: 8.0,
                 },),
             )?;
         }
 
+        // show whether the sounds are muted in the top-right corner
+        if !self.sounds.enabled {
+            let width = self.muted_text.width(ctx);
+            graphics::draw(
+                ctx,
+                &self.muted_text,
+                (Point2 {
+                    x: screen_size.0 - width as f32 - 8.0,
+                    y: 8.0,
+                },),
+            )?;
+        }
+
+        // show the tick rate in the bottom-right corner
+        if self.show_tick_rate {
+            let rate = 1.0 / self.game.update_interval().as_secs_f32();
+            let slow = if self.game.is_slow_motion() {
+                " (slow)"
+            } else {
+                ""
+            };
+            let text = Text::new(format!("{:.1} ticks/s{}", rate, slow));
+            let (width, height) = text.dimensions(ctx);
+            graphics::draw(
+                ctx,
+                &text,
+                (Point2 {
+                    x: screen_size.0 - width as f32 - 8.0,
+                    y: screen_size.1 - height as f32 - 8.0,
+                },),
+            )?;
+        }
+
+        // show the game-over (or win) screen (which the leaderboard replaces) just above the center
+        // note: `Text` caches its own dimensions, so measuring it every frame is cheap
+        if let (Some(text), false) = (&self.finished_text, self.state == GameState::Leaderboard) {
+            let height = text.height(ctx) as f32;
+            draw_centered_text(ctx, text, -height * 0.5)?;
+        }
+
+        // show the initials typed so far (with a placeholder for the rest) below it
+        if self.state == GameState::EnteringInitials {
+            let placeholder = "_".repeat(INITIALS_LENGTH - self.initials.len());
+            let text = Text::new(
+                TextFragment::new(format!("{}{}", self.initials, placeholder))
+                    .scale(Scale::uniform(DEFAULT_FONT_SCALE * 4.0)),
+            );
+            let height = text.height(ctx) as f32;
+            draw_centered_text(ctx, &text, height)?;
+        }
+
+        // show the leaderboard
+        if self.state == GameState::Leaderboard {
+            draw_centered_text(ctx, &self.leaderboard_text, 0.0)?;
+        }
+
+        // show the start menu (the title just above the center, and the instructions below it)
+        if self.state == GameState::Menu {
+            let title_height = self.title_text.height(ctx) as f32;
+            draw_centered_text(ctx, &self.title_text, -title_height * 0.5)?;
+            let start_height = self.start_text.height(ctx) as f32;
+            draw_centered_text(ctx, &self.start_text, start_height * 1.5)?;
+        }
+
+        // show the countdown before the game resumes
+        if let Some(number) = self
+            .resume_countdown
+            .and_then(|unpaused_at| resume_countdown_number(unpaused_at.elapsed()))
+        {
+            let text = Text::new(
+                TextFragment::new(number.to_string())
+                    .scale(Scale::uniform(DEFAULT_FONT_SCALE * 6.0)),
+            );
+            draw_centered_text(ctx, &text, 0.0)?;
+        }
+
+        // show the settings menu
+        if self.state == GameState::Settings {
+            draw_centered_text(ctx, &self.settings_text(), 0.0)?;
+        }
+
+        // show the pause screen
+        if self.state == GameState::Paused {
+            draw_centered_text(ctx, &self.paused_text, 0.0)?;
+        }
+
         graphics::present(ctx)?;
+        // dump the frame if recording, but stop at the first error instead of failing every frame
+        if let Some(recorder) = &mut self.frame_recorder {
+            if let Err(error) = recorder.capture(ctx, self.game.tick()) {
+                warn!("Failed to record a frame, no longer recording: {}", error);
+                self.frame_recorder = None;
+            }
+        }
+        // don't draw more often than the cap (the updates catch up with the
+        // time that passed in the meantime, see `update`)
+        if let Some(limiter) = &mut self.frame_limiter {
+            thread::sleep(limiter.wait(Instant::now()));
+        }
         timer::yield_now();
         Ok(())
     }
@@ -164,21 +1094,171 @@ impl EventHandler for Game {
         ctx: &mut Context,
         keycode: event::KeyCode,
         _keymods: input::keyboard::KeyMods,
-        _repeat: bool,
+        repeat: bool,
     ) {
+        // a held key is repeated by the keyboard, which would flip the toggles back and
+        // forth (and turning again and again could only go the same way), so the repeats
+        // are ignored, unless a held direction key should keep steering
+        if repeat && !(self.config.key_repeat && self.is_direction_key(keycode)) {
+            return;
+        }
         // quit the game
-        if keycode == KeyCode::Escape {
+        if keycode == self.keys.quit {
             event::quit(ctx);
         }
-        // update the direction
-        else if let Some(direction) = Direction::from_keycode(keycode) {
-            // this method may fail if the direction is not orthogonal,
-            // but we don't especially care ;)
-            let _ = self.snake.set_direction(direction);
+        // type the initials of a new top 10 score (which takes over the keyboard)
+        else if self.state == GameState::EnteringInitials {
+            self.type_initial(keycode);
+        }
+        // choose and change the options of the settings menu (which takes over the keyboard,
+        // and is closed again with O)
+        else if self.state == GameState::Settings {
+            let direction = self
+                .keys
+                .direction(keycode)
+                .or_else(|| Direction::from_wasd_keycode(keycode));
+            match direction {
+                Some(Direction::Up) => self.settings_menu.move_cursor(true),
+                Some(Direction::Down) => self.settings_menu.move_cursor(false),
+                Some(Direction::Left) => self.change_setting(false),
+                Some(Direction::Right) => self.change_setting(true),
+                None if keycode == KeyCode::O => self.toggle_settings(),
+                None => {}
+            }
+        }
+        // open / close the leaderboard (between games, where it goes before the directions
+        // so that it still works when it's bound to one of them)
+        else if keycode == self.keys.leaderboard && self.state.toggle_leaderboard() != self.state
+        {
+            self.toggle_leaderboard();
+        }
+        // update the direction (player one uses the bound keys, and player two
+        // uses WASD in a two-player game, which also steers player one otherwise), before
+        // the letter toggles below so that a bound key always steers
+        else if let Some(direction) = self.keys.direction(keycode) {
+            self.steer(Player::One, direction);
+        } else if let Some(direction) = Direction::from_wasd_keycode(keycode) {
+            let player = if self.game.is_two_players() {
+                Player::Two
+            } else {
+                Player::One
+            };
+            self.steer(player, direction);
+        }
+        // open the settings menu
+        else if keycode == KeyCode::O {
+            self.toggle_settings();
+        }
+        // pause / unpause the game
+        else if keycode == self.keys.pause {
+            self.toggle_pause();
+        }
+        // switch between fullscreen and windowed
+        else if keycode == KeyCode::F11 {
+            self.toggle_fullscreen(ctx);
+        }
+        // switch to the next theme
+        else if keycode == KeyCode::T {
+            self.cycle_theme();
+        }
+        // show / hide the grid lines
+        else if keycode == KeyCode::G {
+            self.show_grid = !self.show_grid;
+        }
+        // show / hide the tick rate
+        else if keycode == KeyCode::F3 {
+            self.show_tick_rate = !self.show_tick_rate;
+        }
+        // draw the snakes at their cells in the game (without sliding them)
+        else if keycode == KeyCode::F6 {
+            self.show_logical_cells = !self.show_logical_cells;
+        }
+        // dump the board to the log
+        else if keycode == KeyCode::F4 {
+            info!("\n{}", self.game.debug_board());
+        }
+        // slow the game down while held
+        else if keycode == KeyCode::LShift {
+            self.game.set_slow_motion(true);
+        }
+        // run a single tick (only while paused)
+        else if keycode == KeyCode::Period && self.state == GameState::Paused {
+            self.game.request_step();
+        }
+        // show / hide the wrap indicator
+        else if keycode == KeyCode::I {
+            self.show_wrap_indicator = !self.show_wrap_indicator;
+        }
+        // switch the snake gradient on / off
+        else if keycode == KeyCode::B {
+            self.gradient = !self.gradient;
+        }
+        // switch the checkerboard background on / off
+        else if keycode == KeyCode::C {
+            self.toggle_checkerboard();
+        }
+        // switch between crisp and smooth scaling of the sprites
+        else if keycode == KeyCode::F {
+            self.toggle_filtering();
+        }
+        // save / load the current run
+        else if keycode == KeyCode::F5 {
+            if let Err(error) = self.save(&self.save_path) {
+                warn!(
+                    "Failed to save the game to `{}`: {}",
+                    self.save_path.display(),
+                    error
+                );
+            }
+        } else if keycode == KeyCode::F9 {
+            let path = self.save_path.clone();
+            if let Err(error) = self.load(&path) {
+                warn!(
+                    "Failed to load the game from `{}`: {}",
+                    path.display(),
+                    error
+                );
+            }
+        }
+        // mute / unmute the sounds
+        else if keycode == KeyCode::M {
+            self.toggle_audio();
+        }
+        // raise / lower the volume
+        else if keycode == KeyCode::Equals || keycode == KeyCode::Add {
+            self.change_volume(VOLUME_STEP);
+        } else if keycode == KeyCode::Minus || keycode == KeyCode::Subtract {
+            self.change_volume(-VOLUME_STEP);
+        }
+        // start the game from the menu
+        else if self.state == GameState::Menu && keycode == self.keys.restart {
+            self.start();
         }
         // restart the game
-        else if self.game_over && keycode == KeyCode::Return {
-            self.restart(ctx);
+        else if (self.state.is_finished() || self.state == GameState::Leaderboard)
+            && keycode == self.keys.restart
+        {
+            self.restart();
+        }
+    }
+
+    fn key_up_event(
+        &mut self,
+        _ctx: &mut Context,
+        keycode: KeyCode,
+        _keymods: input::keyboard::KeyMods,
+    ) {
+        // the game is only slowed down while the key is held
+        if keycode == KeyCode::LShift {
+            self.game.set_slow_motion(false);
+        }
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
+        // keep the whole grid visible and undistorted (letterboxing the rest of the window)
+        let coordinates = self.config.screen_coordinates((width, height));
+        if let Err(error) = graphics::set_screen_coordinates(ctx, coordinates) {
+            warn!("Failed to set the screen coordinates: {}", error);
         }
     }
 
@@ -189,26 +1269,68 @@ impl EventHandler for Game {
         }
         // update the direction
         if let Some(direction) = Direction::from_button(btn) {
-            // this method may fail if the direction is not orthogonal,
-            // but we don't especially care ;)
-            let _ = self.snake.set_direction(direction);
+            self.steer(Player::One, direction);
+        }
+        // start the game from the menu
+        else if self.state == GameState::Menu && btn == Button::Start {
+            self.start();
         }
         // restart the game
-        else if self.game_over && btn == Button::Start {
-            self.restart(ctx);
+        else if (self.state.is_finished() || self.state == GameState::Leaderboard)
+            && btn == Button::Start
+        {
+            self.restart();
+        }
+        // the initials can only be typed on the keyboard, so skip them
+        else if self.state == GameState::EnteringInitials && btn == Button::Start {
+            self.toggle_leaderboard();
+        }
+        // pause / unpause the game
+        else if btn == Button::Start {
+            self.toggle_pause();
+        }
+    }
+
+    fn focus_event(&mut self, _ctx: &mut Context, gained: bool) {
+        // pause while the window is in the background, but only resume
+        // when it comes back if it wasn't the player that paused it
+        if !gained && self.state == GameState::Playing {
+            self.toggle_pause();
+            self.auto_paused = true;
+        } else if gained && self.auto_paused {
+            self.toggle_pause();
         }
     }
 
     fn gamepad_axis_event(&mut self, _ctx: &mut Context, axis: Axis, value: f32, _id: GamepadId) {
         if let Some(direction) = Direction::from_axis(axis, value) {
-            // this method may fail if the direction is not orthogonal,
-            // but we don't especially care ;)
-            let _ = self.snake.set_direction(direction);
+            self.steer(Player::One, direction);
         }
     }
 }
 
+/// Helper function that draws `text` centered on the screen (or, with a
+/// `y_offset`, that many pixels below the center).
+fn draw_centered_text(ctx: &mut Context, text: &Text, y_offset: f32) -> GameResult {
+    // the screen is letterboxed evenly on both sides, so its center is unchanged
+    let screen = graphics::screen_coordinates(ctx);
+    let dimensions = text.dimensions(ctx);
+    graphics::draw(
+        ctx,
+        text,
+        (Point2 {
+            x: screen.x + screen.w * 0.5 - dimensions.0 as f32 * 0.5,
+            y: screen.y + screen.h * 0.5 - dimensions.1 as f32 * 0.5 + y_offset,
+        },),
+    )
+}
+
 fn main() {
+    // diagnostics are logged through `log`, and can be turned on
+    // with `RUST_LOG` (e.g. `RUST_LOG=snake_rs=trace`)
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("snake_rs=warn"))
+        .init();
+
     // We add the CARGO_MANIFEST_DIR/resources to the resource paths
     // so that ggez will look in our cargo project directory for files.
     let resource_dir = if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
@@ -219,16 +1341,46 @@ fn main() {
         path::PathBuf::from("./resources")
     };
 
+    // parse the configuration from the command line
+    let mut config = match GameConfig::from_args(env::args().skip(1)) {
+        Ok(config) => config,
+        Err(error) => {
+            eprintln!("Invalid arguments: {}", error);
+            process::exit(1);
+        }
+    };
+
+    // a replay runs with the seed of the recorded run
+    let replay = config
+        .replay
+        .as_ref()
+        .map(|path| match Recording::load_from(path) {
+            Ok(recording) => recording,
+            Err(error) => {
+                eprintln!("Invalid replay `{}`: {}", path.display(), error);
+                process::exit(1);
+            }
+        });
+    if let Some(recording) = &replay {
+        config.seed = Some(recording.seed);
+    }
+
     // create the new context and window with the correct dimensions and title
+    // (the window can be resized freely, see `App::resize_event`)
+    let screen_size = config.screen_size();
     let (mut ctx, mut events_loop) = ContextBuilder::new("Snake", "Eran Cohen")
-        .window_setup(WindowSetup::default().title("Snake"))
-        .window_mode(WindowMode::default().dimensions(SCREEN_SIZE.0, SCREEN_SIZE.1))
+        .window_setup(WindowSetup::default().title("Snake").vsync(config.vsync))
+        .window_mode(
+            WindowMode::default()
+                .dimensions(screen_size.0, screen_size.1)
+                .resizable(true),
+        )
         .add_resource_path(resource_dir)
         .build()
         .unwrap();
     // create a new game
-    let mut game = Game::new(&mut ctx).unwrap();
+    let mut app = App::new(&mut ctx, config, replay).unwrap();
 
     // run the game
-    event::run(&mut ctx, &mut events_loop, &mut game).unwrap();
+    event::run(&mut ctx, &mut events_loop, &mut app).unwrap();
 }
@@ -1,34 +1,179 @@
 use ggez::{
     event::{Axis, Button, KeyCode},
-    graphics::{DrawParam, Rect},
+    graphics::{Color, DrawParam, Rect},
     mint::Point2,
 };
-use rand::{self, Rng};
+use serde::{Deserialize, Serialize};
+use std::{io, str::FromStr, time::Duration};
 
-/// The size of out game board in terms of how many grid
-/// cells it takes up.
+/// The default size of out game board in terms of how many grid
+/// cells it takes up (see `GameConfig`).
 pub const GRID_SIZE: (i16, i16) = (30, 20);
-/// The pixel size of each tile.
+/// The default pixel size of each tile (see `GameConfig`).
 pub const GRID_CELL_SIZE: (i16, i16) = (32, 32);
 
-/// The size of the window.
-pub const SCREEN_SIZE: (f32, f32) = (
-    GRID_SIZE.0 as f32 * GRID_CELL_SIZE.0 as f32,
-    GRID_SIZE.1 as f32 * GRID_CELL_SIZE.1 as f32,
-);
-
 /// The size of a sprite.
 pub const SPRITE_SIZE: (i16, i16) = (32, 32);
-/// The ratio of the cell size to sprite size
-pub const SPRITE_CELL_RATIO: (f32, f32) = (
-    (GRID_CELL_SIZE.0 as f32) / (SPRITE_SIZE.0 as f32),
-    (GRID_CELL_SIZE.1 as f32) / (SPRITE_SIZE.1 as f32),
-);
 
-/// The number of updates we want to run each second.
-pub const UPDATES_PER_SECOND: f32 = 8.0;
-/// The number of milliseconds per each update.
-pub const MILLIS_PER_UPDATE: u64 = (1000.0 / UPDATES_PER_SECOND) as u64;
+/// The fewest number of milliseconds per each update, no matter how long the snake gets.
+pub const MIN_MILLIS_PER_UPDATE: u64 = 50;
+/// The length of a newly created snake (head + tail).
+pub const START_LENGTH: usize = 2;
+/// The number of segments that the snake grows by for each piece of food eaten.
+pub const GROWTH_PER_FOOD: usize = 1;
+/// The number of recent head positions that the ghost trail of the snake shows.
+pub const TRAIL_LENGTH: usize = 8;
+/// How many times longer each tick lasts while the game is in slow motion.
+pub const SLOW_MOTION_FACTOR: u32 = 4;
+/// The maximum number of turns that can be queued up before the snake moves.
+pub const MAX_QUEUED_DIRECTIONS: usize = 2;
+
+/// The number of segments that a snake grows by between
+/// each speed up of the `SpeedCurve::Stepped` curve.
+pub const SPEED_STEP: usize = 5;
+/// How many segments' worth of speed up the `SpeedCurve::Logarithmic`
+/// curve gives for each time that the growth of the snake multiplies by `e`.
+pub const LOGARITHMIC_SPEED_SCALE: f64 = 8.0;
+
+/// Calculates how far along (between 0.0 and 1.0) the game is from the
+/// last update to the next one, `elapsed` time after the last update.
+///
+/// The time is measured on the wall clock, so a stalled frame can take
+/// it past the next update; it's clamped so that the snake is never drawn
+/// further than where that update will put it.
+pub fn update_segment(elapsed: Duration, interval: Duration) -> f32 {
+    (elapsed.as_secs_f32() / interval.as_secs_f32()).clamp(0.0, 1.0)
+}
+
+/// Determines how the snakes slide between updates (see `update_segment`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Easing {
+    /// The snakes slide at a steady pace.
+    #[default]
+    Linear,
+    /// The snakes start and end each slide slowly, and rush through the
+    /// middle of it, which feels snappier.
+    EaseInOut,
+}
+
+impl Easing {
+    /// Eases the given `update_segment` (between 0.0 and 1.0), which keeps
+    /// both of its ends in place (so the snakes are still drawn at their cells).
+    pub fn apply(self, segment: f32) -> f32 {
+        match self {
+            Easing::Linear => segment,
+            Easing::EaseInOut => segment * segment * (3.0 - 2.0 * segment),
+        }
+    }
+}
+
+impl FromStr for Easing {
+    type Err = SnakeError;
+
+    fn from_str(s: &str) -> SnakeResult<Self> {
+        match s {
+            "linear" => Ok(Easing::Linear),
+            "ease-in-out" => Ok(Easing::EaseInOut),
+            _ => Err(SnakeError::ArgumentError(format!(
+                "Unknown easing `{}` (expected `linear` or `ease-in-out`)",
+                s
+            ))),
+        }
+    }
+}
+
+/// How many updates' worth of time can pass between two frames before
+/// the game is considered stalled (e.g. while the window is dragged, or
+/// the app is in the background).
+///
+/// A stalled game doesn't catch up on the updates it missed: it carries
+/// on from where it was when the stall ended, as if it was paused.
+pub const MAX_STALLED_UPDATES: u32 = 3;
+
+/// Checks whether the game stalled, given the time `elapsed` since the
+/// last frame, for updates of the given `interval` (see `MAX_STALLED_UPDATES`).
+pub fn is_stalled(elapsed: Duration, interval: Duration) -> bool {
+    elapsed > interval * MAX_STALLED_UPDATES
+}
+
+/// The number of points awarded for each piece of food eaten.
+pub const POINTS_PER_FOOD: u32 = 1;
+/// How many times more points a bonus food is worth than a normal one.
+pub const BONUS_FOOD_MULTIPLIER: u32 = 5;
+/// The most updates that can pass between eating two foods for the second
+/// one to continue the combo (see `combo_multiplier`), after which it resets.
+pub const COMBO_WINDOW: u32 = 25;
+/// The highest multiplier that a combo can reach.
+pub const MAX_COMBO_MULTIPLIER: u32 = 4;
+/// The probability that a bonus food spawns after eating normal food.
+pub const BONUS_FOOD_CHANCE: f64 = 0.15;
+/// The number of updates a bonus food stays on the board before disappearing.
+pub const BONUS_FOOD_LIFETIME: u32 = 40;
+/// The probability that a poison food spawns after eating normal food.
+pub const POISON_FOOD_CHANCE: f64 = 0.1;
+/// The number of updates a poison food stays on the board before disappearing.
+pub const POISON_FOOD_LIFETIME: u32 = 60;
+/// The probability that a power-up spawns after eating normal food
+/// (with power-ups enabled, see `GameConfig::power_ups`).
+pub const POWER_UP_CHANCE: f64 = 0.1;
+/// The number of updates a power-up stays on the board before disappearing.
+pub const POWER_UP_LIFETIME: u32 = 60;
+/// The number of updates that the magnet power-up lasts for once it's picked up.
+pub const MAGNET_DURATION: u32 = 40;
+/// The number of updates that the speed boost power-up lasts for once it's picked up.
+pub const SPEED_BOOST_DURATION: u32 = 50;
+/// The number of updates that the shield power-up lasts for once it's picked up.
+pub const SHIELD_DURATION: u32 = 40;
+/// How many times faster the game runs with the speed boost. It's on top of
+/// the speed up as the snakes grow (see `SpeedCurve::millis_per_update`), so the two multiply.
+pub const SPEED_BOOST_FACTOR: f64 = 1.5;
+/// How far (in cells, diagonals included) from the head of a snake food doesn't
+/// spawn, with the safe spawn option (see `GameConfig::safe_spawn`).
+pub const SAFE_SPAWN_RADIUS: i16 = 1;
+/// The number of updates between each move of the food (when it's moving).
+pub const FOOD_MOVE_INTERVAL: u64 = 5;
+
+/// Returns how many times more points a food is worth when it's the `combo`th
+/// one in a row that was eaten quickly (see `COMBO_WINDOW`): one more for
+/// each food in the combo, up to `MAX_COMBO_MULTIPLIER`.
+pub fn combo_multiplier(combo: u32) -> u32 {
+    combo.clamp(1, MAX_COMBO_MULTIPLIER)
+}
+
+/// How long the head of the snake pulses for after eating food.
+pub const EAT_PULSE_MILLIS: u64 = 150;
+/// How much bigger the head of the snake is at the start of the pulse.
+pub const EAT_PULSE_SCALE: f32 = 0.25;
+
+/// Returns how big (relative to its normal size) the head of the snake is drawn,
+/// given the time `elapsed` since it ate: a pulse that shrinks back over
+/// `EAT_PULSE_MILLIS`.
+pub fn eat_pulse_scale(elapsed: Duration) -> f32 {
+    let progress = elapsed.as_secs_f32() / Duration::from_millis(EAT_PULSE_MILLIS).as_secs_f32();
+    1.0 + EAT_PULSE_SCALE * (1.0 - progress).max(0.0)
+}
+
+/// How long the screen shakes for after the snake dies.
+pub const DEATH_SHAKE_MILLIS: u64 = 300;
+/// The largest offset (in pixels) of the screen while it shakes.
+pub const DEATH_SHAKE_MAGNITUDE: f32 = 6.0;
+
+/// How long the countdown before the game resumes (after unpausing) lasts.
+pub const RESUME_COUNTDOWN_MILLIS: u64 = 1500;
+/// The number that the resume countdown counts down from.
+pub const RESUME_COUNTDOWN_FROM: u64 = 3;
+
+/// Returns the number to show `elapsed` time into the resume
+/// countdown (`RESUME_COUNTDOWN_FROM`, ..., 2, 1), or `None` once it's over.
+pub fn resume_countdown_number(elapsed: Duration) -> Option<u64> {
+    let step = RESUME_COUNTDOWN_MILLIS / RESUME_COUNTDOWN_FROM;
+    let elapsed = elapsed.as_millis() as u64;
+    if elapsed < RESUME_COUNTDOWN_MILLIS {
+        Some(RESUME_COUNTDOWN_FROM - elapsed / step)
+    } else {
+        None
+    }
+}
 
 /// This trait provides an "arithmetic" modulo function,
 /// which works well for wrapping negative values.
@@ -47,15 +192,59 @@ where
 
 /// Contains all relevent errors
 /// for our game.
-#[derive(Debug, Clone)]
+///
+/// More kinds of errors may be added, so matching on them
+/// outside of this crate needs a catch-all arm.
+// the message variants keep the existing `...Error` naming
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
 pub enum SnakeError {
+    #[error("{0}")]
     LogicError(String),
+    #[error("{0}")]
+    ArgumentError(String),
+    /// Saving or loading a game failed.
+    #[error("{0}")]
+    SaveError(String),
+    /// Loading a level failed (see `Level::parse`).
+    #[error("{0}")]
+    LevelError(String),
+    /// The snake was steered in the opposite direction
+    /// of where it's going (which is the given `Direction`).
+    #[error("Can't turn {:?} while going {0:?}", .0.inverse())]
+    InvalidDirection(Direction),
+    /// Reading or writing a file failed.
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    /// A file (a save, a replay, the settings, ...) couldn't be parsed or written out.
+    #[error("{0}")]
+    Parse(String),
 }
 pub type SnakeResult<T = ()> = Result<T, SnakeError>;
 
+// the different formats all end up as a `Parse` error, so these can't be `#[from]`s
+impl From<serde_json::Error> for SnakeError {
+    fn from(error: serde_json::Error) -> Self {
+        SnakeError::Parse(error.to_string())
+    }
+}
+
+impl From<toml::de::Error> for SnakeError {
+    fn from(error: toml::de::Error) -> Self {
+        SnakeError::Parse(error.to_string())
+    }
+}
+
+impl From<toml::ser::Error> for SnakeError {
+    fn from(error: toml::ser::Error) -> Self {
+        SnakeError::Parse(error.to_string())
+    }
+}
+
 /// Represents all the possible directions
 /// that our snake can move.
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum Direction {
     Left,
     Right,
@@ -63,6 +252,14 @@ pub enum Direction {
     Down,
 }
 
+/// All of the `Direction`s.
+pub const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
 impl Direction {
     /// Allows us to easily get the inverse of a `Direction`.
     ///
@@ -79,9 +276,17 @@ impl Direction {
 
     /// Converts between a `ggez` `KeyCode` and the `Direction` it represents.
     ///
+    /// Both the arrow keys and WASD are supported.
+    ///
     /// Not every `KeyCode` represents a `Direction`, so `None` is returned
     /// if this is the case.
     pub fn from_keycode(keycode: KeyCode) -> Option<Self> {
+        Self::from_arrow_keycode(keycode).or_else(|| Self::from_wasd_keycode(keycode))
+    }
+
+    /// Like `from_keycode`, but only for the arrow keys
+    /// (which player one steers with in a two-player game).
+    pub fn from_arrow_keycode(keycode: KeyCode) -> Option<Self> {
         match keycode {
             KeyCode::Up => Some(Direction::Up),
             KeyCode::Down => Some(Direction::Down),
@@ -91,6 +296,18 @@ impl Direction {
         }
     }
 
+    /// Like `from_keycode`, but only for WASD
+    /// (which player two steers with in a two-player game).
+    pub fn from_wasd_keycode(keycode: KeyCode) -> Option<Self> {
+        match keycode {
+            KeyCode::W => Some(Direction::Up),
+            KeyCode::S => Some(Direction::Down),
+            KeyCode::A => Some(Direction::Left),
+            KeyCode::D => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
     /// Converts between a `ggez` `Button` and the `Direction` it represents.
     ///
     /// Not every `Button` represents a `Direction`, so `None` is returned
@@ -109,47 +326,254 @@ impl Direction {
     ///
     /// Not every `Axis` represents a `Direction` in our case,   
     /// so `None` is returned if the is the case.
+    /// Both the left and the right sticks steer the snake.
     ///
     /// Note that we also have a deadzone in our axis to prevent over-sensitive behavior.
     pub fn from_axis(axis: Axis, value: f32) -> Option<Self> {
-        const CUTOFF: f32 = 0.4;
         match axis {
-            Axis::RightStickX => {
-                if value > CUTOFF {
-                    Some(Direction::Right)
-                } else if value < -CUTOFF {
-                    Some(Direction::Left)
-                } else {
-                    None
-                }
+            Axis::LeftStickX | Axis::RightStickX => {
+                Self::past_deadzone(value, Direction::Right, Direction::Left)
             }
-            Axis::RightStickY => {
-                if value > CUTOFF {
-                    Some(Direction::Up)
-                } else if value < -CUTOFF {
-                    Some(Direction::Down)
-                } else {
-                    None
-                }
+            Axis::LeftStickY | Axis::RightStickY => {
+                Self::past_deadzone(value, Direction::Up, Direction::Down)
             }
             _ => None,
         }
     }
+
+    /// Helper function that picks the `positive` or the `negative` direction of
+    /// an axis by its `value`, or `None` if it's within the deadzone.
+    fn past_deadzone(value: f32, positive: Self, negative: Self) -> Option<Self> {
+        const CUTOFF: f32 = 0.4;
+        if value > CUTOFF {
+            Some(positive)
+        } else if value < -CUTOFF {
+            Some(negative)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for Direction {
+    type Err = SnakeError;
+
+    fn from_str(s: &str) -> SnakeResult<Self> {
+        match s {
+            "up" => Ok(Direction::Up),
+            "down" => Ok(Direction::Down),
+            "left" => Ok(Direction::Left),
+            "right" => Ok(Direction::Right),
+            _ => Err(SnakeError::ArgumentError(format!(
+                "Unknown direction `{}` (expected `up`, `down`, `left` or `right`)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Determines how the game speeds up as the snakes grow (see `SpeedCurve::millis_per_update`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SpeedCurve {
+    /// Every segment speeds the game up by the same amount.
+    #[default]
+    Linear,
+    /// The game speeds up quickly at first, and less and less as the snake grows.
+    Logarithmic,
+    /// The game speeds up every `SPEED_STEP` segments (and stays
+    /// at the same speed in between).
+    Stepped,
+}
+
+impl SpeedCurve {
+    /// Returns the number of segments that the game is sped up for (each by
+    /// `Difficulty::millis_speedup_per_segment`), once the snake grew by `growth` segments.
+    ///
+    /// It never decreases as the snake grows, so the game never slows back down.
+    pub fn speedup_segments(self, growth: usize) -> usize {
+        match self {
+            SpeedCurve::Linear => growth,
+            SpeedCurve::Logarithmic => (LOGARITHMIC_SPEED_SCALE * (growth as f64).ln_1p()) as usize,
+            SpeedCurve::Stepped => growth / SPEED_STEP * SPEED_STEP,
+        }
+    }
+
+    /// Calculates the number of milliseconds per each update for a snake
+    /// of the given length, on the given `Difficulty`, along this curve.
+    ///
+    /// The longer the snake, the faster the game (down to `MIN_MILLIS_PER_UPDATE`).
+    pub fn millis_per_update(self, length: usize, difficulty: Difficulty) -> u64 {
+        let growth = self.speedup_segments(length.saturating_sub(START_LENGTH)) as u64;
+        let millis = (1000.0 / difficulty.updates_per_second()) as u64;
+        millis
+            .saturating_sub(growth * difficulty.millis_speedup_per_segment())
+            .max(MIN_MILLIS_PER_UPDATE)
+    }
+}
+
+impl FromStr for SpeedCurve {
+    type Err = SnakeError;
+
+    fn from_str(s: &str) -> SnakeResult<Self> {
+        match s {
+            "linear" => Ok(SpeedCurve::Linear),
+            "logarithmic" => Ok(SpeedCurve::Logarithmic),
+            "stepped" => Ok(SpeedCurve::Stepped),
+            _ => Err(SnakeError::ArgumentError(format!(
+                "Unknown speed curve `{}` (expected `linear`, `logarithmic` or `stepped`)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Determines what happens when the snake reaches an edge of the board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum EdgeBehavior {
+    /// The snake wraps around to the opposite edge.
+    Wrap,
+    /// The snake dies when it hits the edge.
+    Wall,
+    /// The snake wraps around the left and right edges,
+    /// but dies when it hits the top or the bottom one.
+    WrapX,
+    /// The snake wraps around the top and bottom edges,
+    /// but dies when it hits the left or the right one.
+    WrapY,
+}
+
+impl EdgeBehavior {
+    /// Whether the snake wraps around the left and right edges.
+    pub fn wraps_x(self) -> bool {
+        matches!(self, EdgeBehavior::Wrap | EdgeBehavior::WrapX)
+    }
+
+    /// Whether the snake wraps around the top and bottom edges.
+    pub fn wraps_y(self) -> bool {
+        matches!(self, EdgeBehavior::Wrap | EdgeBehavior::WrapY)
+    }
+}
+
+impl FromStr for EdgeBehavior {
+    type Err = SnakeError;
+
+    fn from_str(s: &str) -> SnakeResult<Self> {
+        match s {
+            "wrap" => Ok(EdgeBehavior::Wrap),
+            "wall" => Ok(EdgeBehavior::Wall),
+            "wrap-x" => Ok(EdgeBehavior::WrapX),
+            "wrap-y" => Ok(EdgeBehavior::WrapY),
+            _ => Err(SnakeError::ArgumentError(format!(
+                "Unknown edge behavior `{}` (expected `wrap`, `wall`, `wrap-x` or `wrap-y`)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Determines how fast the game is.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    /// Returns the next harder difficulty (or this one, if it's the hardest).
+    pub fn harder(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal | Difficulty::Hard => Difficulty::Hard,
+        }
+    }
+
+    /// Returns the next easier difficulty (or this one, if it's the easiest).
+    pub fn easier(self) -> Self {
+        match self {
+            Difficulty::Hard => Difficulty::Normal,
+            Difficulty::Normal | Difficulty::Easy => Difficulty::Easy,
+        }
+    }
+
+    /// The number of updates run each second at the start of the game.
+    pub fn updates_per_second(self) -> f32 {
+        match self {
+            Difficulty::Easy => 6.0,
+            Difficulty::Normal => 8.0,
+            Difficulty::Hard => 12.0,
+        }
+    }
+
+    /// The number of milliseconds shaved off each update for every segment the snake grows.
+    pub fn millis_speedup_per_segment(self) -> u64 {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Normal => 2,
+            Difficulty::Hard => 3,
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = SnakeError;
+
+    fn from_str(s: &str) -> SnakeResult<Self> {
+        match s {
+            "easy" => Ok(Difficulty::Easy),
+            "normal" => Ok(Difficulty::Normal),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(SnakeError::ArgumentError(format!(
+                "Unknown difficulty `{}` (expected `easy`, `normal` or `hard`)",
+                s
+            ))),
+        }
+    }
+}
+
+/// The rules that decide when the game ends.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GameMode {
+    /// The game goes on until the snake dies (or fills the board).
+    #[default]
+    Endless,
+    /// The game also ends once it has been played for `limit`,
+    /// and the goal is to score as much as possible until then.
+    TimeAttack { limit: Duration },
+    /// The game never ends: a snake that dies starts over instead
+    /// (and its deaths are counted, see `Game::deaths`).
+    Practice,
 }
 
 /// Represents the possible things that the
 /// snake could have eaten each update.
 ///
-/// It could have either eaten a piece of `Food` or
-/// itself if its head ran into its body.
-#[derive(Clone, Copy)]
+/// It could have either eaten a piece of `Food`,
+/// itself if its head ran into its body or a wall
+/// if it tried to leave the board (see `EdgeBehavior::Wall`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Ate {
     Itself,
-    Food,
+    /// The `Food` that was eaten, and the points it's worth.
+    Food(u32),
+    /// A poison food, which shrunk the snake by one segment.
+    Poison,
+    /// A poison food, when the snake was already too short to shrink.
+    FatalPoison,
+    Wall,
 }
 
 /// Represents a location on the grid / game board.
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct GridPosition {
     pub x: i16,
     pub y: i16,
@@ -160,33 +584,79 @@ impl GridPosition {
         Self { x, y }
     }
 
-    /// Creates a random `GridPosition` from `(0, 0)` to `(max_x, max_y)`.
-    pub fn random(max_x: i16, max_y: i16) -> Self {
-        let mut rng = rand::thread_rng();
-        (
-            rng.gen_range::<i16, i16, i16>(0, max_x),
-            rng.gen_range::<i16, i16, i16>(0, max_y),
-        )
-            .into()
+    /// Converts the `GridPosition` to the screen coordinates
+    /// of its top-left corner, given the pixel size of each tile.
+    pub fn to_point(self, cell_size: (i16, i16)) -> Point2<f32> {
+        Point2 {
+            x: self.x as f32 * cell_size.0 as f32,
+            y: self.y as f32 * cell_size.1 as f32,
+        }
     }
 
-    /// Created a `GridPosition` from another position and a `Direction`.
-    pub fn new_from_move(position: GridPosition, direction: Direction) -> Self {
+    /// Returns the corners of an arrow inside of this cell (given the pixel size
+    /// of each tile) that points in `direction`, starting with its tip.
+    pub fn arrow_points(self, direction: Direction, cell_size: (i16, i16)) -> [Point2<f32>; 3] {
+        let corner = self.to_point(cell_size);
+        let (width, height) = (cell_size.0 as f32, cell_size.1 as f32);
+        // the arrow is inset by a quarter of the cell on every side
+        let point = |x: f32, y: f32| Point2 {
+            x: corner.x + width * x,
+            y: corner.y + height * y,
+        };
+        match direction {
+            Direction::Up => [point(0.5, 0.25), point(0.75, 0.75), point(0.25, 0.75)],
+            Direction::Down => [point(0.5, 0.75), point(0.25, 0.25), point(0.75, 0.25)],
+            Direction::Left => [point(0.25, 0.5), point(0.75, 0.25), point(0.75, 0.75)],
+            Direction::Right => [point(0.75, 0.5), point(0.25, 0.75), point(0.25, 0.25)],
+        }
+    }
+
+    /// Created a `GridPosition` from another position and a `Direction`,
+    /// wrapping around the edges of a board of size `grid_size`.
+    pub fn new_from_move(
+        position: GridPosition,
+        direction: Direction,
+        grid_size: (i16, i16),
+    ) -> Self {
         match direction {
             Direction::Up => {
-                GridPosition::new(position.x, (position.y - 1).modulo_signed(GRID_SIZE.1))
+                GridPosition::new(position.x, (position.y - 1).modulo_signed(grid_size.1))
             }
             Direction::Down => {
-                GridPosition::new(position.x, (position.y + 1).modulo_signed(GRID_SIZE.1))
+                GridPosition::new(position.x, (position.y + 1).modulo_signed(grid_size.1))
             }
             Direction::Left => {
-                GridPosition::new((position.x - 1).modulo_signed(GRID_SIZE.0), position.y)
+                GridPosition::new((position.x - 1).modulo_signed(grid_size.0), position.y)
             }
             Direction::Right => {
-                GridPosition::new((position.x + 1).modulo_signed(GRID_SIZE.0), position.y)
+                GridPosition::new((position.x + 1).modulo_signed(grid_size.0), position.y)
             }
         }
     }
+
+    /// Like `new_from_move`, but respects the given `EdgeBehavior`.
+    ///
+    /// Returns `None` if the move would leave a board of size `grid_size`
+    /// across an edge that the snake doesn't wrap around (e.g. in `EdgeBehavior::Wall`).
+    pub fn try_move(
+        position: GridPosition,
+        direction: Direction,
+        grid_size: (i16, i16),
+        edges: EdgeBehavior,
+    ) -> Option<Self> {
+        let wrapped = GridPosition::new_from_move(position, direction, grid_size);
+        let crossed_wall = match direction {
+            Direction::Up => wrapped.y > position.y && !edges.wraps_y(),
+            Direction::Down => wrapped.y < position.y && !edges.wraps_y(),
+            Direction::Left => wrapped.x > position.x && !edges.wraps_x(),
+            Direction::Right => wrapped.x < position.x && !edges.wraps_x(),
+        };
+        if crossed_wall {
+            None
+        } else {
+            Some(wrapped)
+        }
+    }
 }
 
 impl From<(i16, i16)> for GridPosition {
@@ -195,21 +665,11 @@ impl From<(i16, i16)> for GridPosition {
     }
 }
 
-/// Helper implementation to eaily convert our `GridPosition`
-/// to screen coordinates.
-impl From<GridPosition> for Point2<f32> {
-    fn from(pos: GridPosition) -> Self {
-        Self {
-            x: (pos.x * GRID_CELL_SIZE.0) as f32,
-            y: (pos.y * GRID_CELL_SIZE.1) as f32,
-        }
-    }
-}
-
 /// The various sprites available in the spritesheet.
 ///
 /// Use in conjuction with `Game::get_param` to easily get
 /// the correct `DrawParam` that draws the asked for sprite.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum Sprite {
     /// The head sprites.
     ///
@@ -229,11 +689,25 @@ pub enum Sprite {
     Tail(Direction),
     Rabit,
     Grass,
+    /// The obstacle sprite.
+    ///
+    /// The spritesheet has no room left, so this is a darkened `Grass`.
+    Wall,
+    /// The poison food sprite, which is a purple `Rabit` (for the same reason).
+    Poison,
+    /// The portal sprite, which is a blue `Grass` (for the same reason).
+    Portal,
+    /// The magnet power-up sprite, which is a cyan `Rabit` (for the same reason).
+    Magnet,
+    /// The speed boost power-up sprite, which is an orange `Rabit` (for the same reason).
+    Speed,
+    /// The shield power-up sprite, which is a pale blue `Rabit` (for the same reason).
+    Shield,
 }
 
 impl From<&Sprite> for DrawParam {
     /// Creates a `DrawParam` with the correct `src` and `rotation` for
-    /// the asked for `Sprite`.
+    /// the asked for `Sprite`, at the sprite's original size.
     ///
     /// Will panic if the directions in the `Sprite::Segment` are not possible.
     fn from(sprite: &Sprite) -> DrawParam {
@@ -271,18 +745,28 @@ impl From<&Sprite> for DrawParam {
                 Direction::Down => Rect::new(0.5, 0.25, 0.25, 0.25),
                 Direction::Left => Rect::new(0.75, 0.25, 0.25, 0.25),
             },
-            Sprite::Rabit => Rect::new(0.5, 0.75, 0.25, 0.25),
-            Sprite::Grass => Rect::new(0.75, 0.75, 0.25, 0.25),
+            Sprite::Rabit | Sprite::Poison | Sprite::Magnet | Sprite::Speed | Sprite::Shield => {
+                Rect::new(0.5, 0.75, 0.25, 0.25)
+            }
+            Sprite::Grass | Sprite::Wall | Sprite::Portal => Rect::new(0.75, 0.75, 0.25, 0.25),
         };
 
-        DrawParam::default()
-            .src(src)
-            .scale([SPRITE_CELL_RATIO.0, SPRITE_CELL_RATIO.1])
+        let param = DrawParam::default().src(src);
+        match sprite {
+            Sprite::Wall => param.color(Color::from_rgb(70, 70, 70)),
+            Sprite::Poison => param.color(Color::from_rgb(150, 60, 200)),
+            Sprite::Portal => param.color(Color::from_rgb(60, 140, 255)),
+            Sprite::Magnet => param.color(Color::from_rgb(0, 230, 230)),
+            Sprite::Speed => param.color(Color::from_rgb(255, 140, 0)),
+            Sprite::Shield => param.color(Color::from_rgb(190, 200, 255)),
+            _ => param,
+        }
     }
 }
 
 /// Contains all the information needed to
 /// describe and display a sprite.
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PositionedSprite {
     pub sprite: Sprite,
     pub position: GridPosition,
@@ -292,10 +776,480 @@ impl PositionedSprite {
     pub fn new(sprite: Sprite, position: GridPosition) -> Self {
         Self { sprite, position }
     }
+
+    /// Creates the `DrawParam` that draws the sprite in its
+    /// position, scaled to fill a tile of the given pixel size.
+    pub fn to_draw_param(&self, cell_size: (i16, i16)) -> DrawParam {
+        DrawParam::from(&self.sprite)
+            .dest(self.position.to_point(cell_size))
+            .scale([
+                cell_size.0 as f32 / SPRITE_SIZE.0 as f32,
+                cell_size.1 as f32 / SPRITE_SIZE.1 as f32,
+            ])
+    }
+
+    /// Scales the given `DrawParam` of this sprite (as returned by `to_draw_param`,
+    /// possibly moved) by `scale`, around the center of its tile.
+    pub fn scale_draw_param(
+        &self,
+        param: DrawParam,
+        cell_size: (i16, i16),
+        scale: f32,
+    ) -> DrawParam {
+        let grow = scale - 1.0;
+        param
+            .dest(Point2 {
+                x: param.dest.x - grow * cell_size.0 as f32 * 0.5,
+                y: param.dest.y - grow * cell_size.1 as f32 * 0.5,
+            })
+            .scale([param.scale.x * scale, param.scale.y * scale])
+    }
+
+    /// Like `to_draw_param`, but moved `amount` of a tile in `direction`,
+    /// which is used to slide the sprite between updates.
+    pub fn to_draw_param_towards(
+        &self,
+        cell_size: (i16, i16),
+        direction: Direction,
+        amount: f32,
+    ) -> DrawParam {
+        let (dx, dy) = match direction {
+            Direction::Up => (0.0, -amount),
+            Direction::Down => (0.0, amount),
+            Direction::Left => (-amount, 0.0),
+            Direction::Right => (amount, 0.0),
+        };
+        let point = self.position.to_point(cell_size);
+        self.to_draw_param(cell_size).dest(Point2 {
+            x: point.x + dx * cell_size.0 as f32,
+            y: point.y + dy * cell_size.1 as f32,
+        })
+    }
 }
 
-impl From<&PositionedSprite> for DrawParam {
-    fn from(ps: &PositionedSprite) -> Self {
-        DrawParam::from(&ps.sprite).dest(ps.position)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn inverse_is_its_own_inverse() {
+        for direction in &DIRECTIONS {
+            assert_ne!(direction.inverse(), *direction);
+            assert_eq!(direction.inverse().inverse(), *direction);
+        }
+    }
+
+    #[test]
+    fn keys_map_to_directions() {
+        assert_eq!(Direction::from_keycode(KeyCode::Up), Some(Direction::Up));
+        assert_eq!(
+            Direction::from_keycode(KeyCode::Down),
+            Some(Direction::Down)
+        );
+        assert_eq!(
+            Direction::from_keycode(KeyCode::Left),
+            Some(Direction::Left)
+        );
+        assert_eq!(
+            Direction::from_keycode(KeyCode::Right),
+            Some(Direction::Right)
+        );
+        assert_eq!(Direction::from_keycode(KeyCode::W), Some(Direction::Up));
+        assert_eq!(Direction::from_keycode(KeyCode::S), Some(Direction::Down));
+        assert_eq!(Direction::from_keycode(KeyCode::A), Some(Direction::Left));
+        assert_eq!(Direction::from_keycode(KeyCode::D), Some(Direction::Right));
+    }
+
+    #[test]
+    fn arrows_and_wasd_can_be_told_apart() {
+        assert_eq!(
+            Direction::from_arrow_keycode(KeyCode::Up),
+            Some(Direction::Up)
+        );
+        assert_eq!(Direction::from_arrow_keycode(KeyCode::W), None);
+        assert_eq!(
+            Direction::from_wasd_keycode(KeyCode::W),
+            Some(Direction::Up)
+        );
+        assert_eq!(Direction::from_wasd_keycode(KeyCode::Up), None);
+    }
+
+    #[test]
+    fn other_keys_are_not_directions() {
+        for keycode in &[KeyCode::Return, KeyCode::Escape, KeyCode::Space, KeyCode::Q] {
+            assert_eq!(Direction::from_keycode(*keycode), None);
+        }
+    }
+
+    #[test]
+    fn buttons_map_to_directions() {
+        assert_eq!(Direction::from_button(Button::DPadUp), Some(Direction::Up));
+        assert_eq!(
+            Direction::from_button(Button::DPadDown),
+            Some(Direction::Down)
+        );
+        assert_eq!(
+            Direction::from_button(Button::DPadLeft),
+            Some(Direction::Left)
+        );
+        assert_eq!(
+            Direction::from_button(Button::DPadRight),
+            Some(Direction::Right)
+        );
+        assert_eq!(Direction::from_button(Button::Start), None);
+    }
+
+    #[test]
+    fn errors_display_their_message() {
+        let error = SnakeError::SaveError("Failed to write `save.json`".to_owned());
+        assert_eq!(error.to_string(), "Failed to write `save.json`");
+        let error = SnakeError::InvalidDirection(Direction::Right);
+        assert_eq!(error.to_string(), "Can't turn Left while going Right");
+    }
+
+    #[test]
+    fn errors_propagate_as_standard_errors() {
+        fn load() -> Result<(), Box<dyn Error>> {
+            Err(SnakeError::LevelError(
+                "Line 2: unknown cell `x`".to_owned(),
+            ))?;
+            Ok(())
+        }
+        assert_eq!(load().unwrap_err().to_string(), "Line 2: unknown cell `x`");
+    }
+
+    #[test]
+    fn io_and_parse_errors_convert() {
+        fn read() -> SnakeResult<u32> {
+            let contents = std::fs::read_to_string("/nonexistent/save.json")?;
+            Ok(serde_json::from_str(&contents)?)
+        }
+        match read() {
+            Err(SnakeError::Io(error)) => assert_eq!(error.kind(), io::ErrorKind::NotFound),
+            result => panic!("Expected an IO error, got {:?}", result),
+        }
+        let error = SnakeError::from(serde_json::from_str::<u32>("nope").unwrap_err());
+        assert!(matches!(error, SnakeError::Parse(_)));
+        assert!(error.source().is_none());
+        let error = SnakeError::from(toml::from_str::<toml::Value>("a = [").unwrap_err());
+        assert!(matches!(error, SnakeError::Parse(_)));
+    }
+
+    #[test]
+    fn directions_are_parsed() {
+        assert_eq!("up".parse::<Direction>().unwrap(), Direction::Up);
+        assert_eq!("left".parse::<Direction>().unwrap(), Direction::Left);
+        assert!("north".parse::<Direction>().is_err());
+    }
+
+    #[test]
+    fn axis_respects_the_deadzone() {
+        assert_eq!(Direction::from_axis(Axis::RightStickX, 0.0), None);
+        assert_eq!(Direction::from_axis(Axis::RightStickX, 0.4), None);
+        assert_eq!(Direction::from_axis(Axis::RightStickX, -0.4), None);
+        assert_eq!(
+            Direction::from_axis(Axis::RightStickX, 0.41),
+            Some(Direction::Right)
+        );
+        assert_eq!(
+            Direction::from_axis(Axis::RightStickX, -0.41),
+            Some(Direction::Left)
+        );
+        assert_eq!(Direction::from_axis(Axis::RightStickY, 0.4), None);
+        assert_eq!(
+            Direction::from_axis(Axis::RightStickY, 0.41),
+            Some(Direction::Up)
+        );
+        assert_eq!(
+            Direction::from_axis(Axis::RightStickY, -0.41),
+            Some(Direction::Down)
+        );
+        assert_eq!(Direction::from_axis(Axis::LeftZ, 1.0), None);
+    }
+
+    #[test]
+    fn left_stick_steers_like_the_right_one() {
+        assert_eq!(Direction::from_axis(Axis::LeftStickX, 0.4), None);
+        assert_eq!(Direction::from_axis(Axis::LeftStickX, -0.4), None);
+        assert_eq!(
+            Direction::from_axis(Axis::LeftStickX, 0.41),
+            Some(Direction::Right)
+        );
+        assert_eq!(
+            Direction::from_axis(Axis::LeftStickX, -0.41),
+            Some(Direction::Left)
+        );
+        assert_eq!(Direction::from_axis(Axis::LeftStickY, -0.4), None);
+        assert_eq!(
+            Direction::from_axis(Axis::LeftStickY, 0.41),
+            Some(Direction::Up)
+        );
+        assert_eq!(
+            Direction::from_axis(Axis::LeftStickY, -0.41),
+            Some(Direction::Down)
+        );
+    }
+
+    #[test]
+    fn the_game_speeds_up_as_the_snake_grows() {
+        assert_eq!(
+            SpeedCurve::Linear.millis_per_update(START_LENGTH, Difficulty::Normal),
+            125
+        );
+        assert_eq!(
+            SpeedCurve::Linear.millis_per_update(START_LENGTH + 10, Difficulty::Normal),
+            105
+        );
+        assert_eq!(
+            SpeedCurve::Linear.millis_per_update(1000, Difficulty::Normal),
+            MIN_MILLIS_PER_UPDATE
+        );
+    }
+
+    #[test]
+    fn harder_difficulties_are_faster() {
+        for length in &[START_LENGTH, START_LENGTH + 10] {
+            let easy = SpeedCurve::Linear.millis_per_update(*length, Difficulty::Easy);
+            let normal = SpeedCurve::Linear.millis_per_update(*length, Difficulty::Normal);
+            let hard = SpeedCurve::Linear.millis_per_update(*length, Difficulty::Hard);
+            assert!(easy > normal && normal > hard);
+        }
+        assert_eq!(
+            SpeedCurve::Linear.millis_per_update(START_LENGTH, Difficulty::Easy),
+            166
+        );
+        assert_eq!(Difficulty::Easy.harder(), Difficulty::Normal);
+        assert_eq!(Difficulty::Hard.harder(), Difficulty::Hard);
+        assert_eq!(Difficulty::Easy.easier(), Difficulty::Easy);
+        assert_eq!(
+            SpeedCurve::Linear.millis_per_update(START_LENGTH, Difficulty::Hard),
+            83
+        );
+    }
+
+    #[test]
+    fn speed_curves_never_slow_down() {
+        let curves = [
+            SpeedCurve::Linear,
+            SpeedCurve::Logarithmic,
+            SpeedCurve::Stepped,
+        ];
+        for &curve in &curves {
+            let intervals: Vec<u64> = (START_LENGTH..200)
+                .map(|length| curve.millis_per_update(length, Difficulty::Normal))
+                .collect();
+            assert!(
+                intervals.windows(2).all(|pair| pair[1] <= pair[0]),
+                "{:?}",
+                curve
+            );
+            assert_eq!(intervals[0], 125);
+        }
+        // the stepped curve only speeds up every few segments
+        let stepped = |growth| {
+            SpeedCurve::Stepped.millis_per_update(START_LENGTH + growth, Difficulty::Normal)
+        };
+        assert_eq!((stepped(4), stepped(5), stepped(9)), (125, 115, 115));
+        // and the logarithmic one is faster at first (the linear one is at 115 after 5
+        // segments), but it's still above the minimum long after the linear one is
+        let logarithmic = |growth| {
+            SpeedCurve::Logarithmic.millis_per_update(START_LENGTH + growth, Difficulty::Normal)
+        };
+        assert!(logarithmic(5) < 115 && logarithmic(40) > MIN_MILLIS_PER_UPDATE);
+        assert_eq!(
+            "stepped".parse::<SpeedCurve>().unwrap(),
+            SpeedCurve::Stepped
+        );
+    }
+
+    #[test]
+    fn arrows_point_in_their_direction() {
+        let [tip, ..] = GridPosition::new(2, 1).arrow_points(Direction::Right, (32, 32));
+        assert_eq!(tip, Point2 { x: 88.0, y: 48.0 });
+        let [tip, ..] = GridPosition::new(0, 0).arrow_points(Direction::Up, (48, 24));
+        assert_eq!(tip, Point2 { x: 24.0, y: 6.0 });
+    }
+
+    #[test]
+    fn modulo_signed_wraps_negative_values() {
+        assert_eq!((-1i16).modulo_signed(30), 29);
+        assert_eq!((-30i16).modulo_signed(30), 0);
+        assert_eq!((-31i16).modulo_signed(30), 29);
+        assert_eq!(0i16.modulo_signed(30), 0);
+        assert_eq!(30i16.modulo_signed(30), 0);
+        assert_eq!(17i16.modulo_signed(30), 17);
+    }
+
+    #[test]
+    fn moving_off_an_edge_wraps_around() {
+        let (width, height) = GRID_SIZE;
+        let wraps = [
+            ((0, 5), Direction::Left, (width - 1, 5)),
+            ((width - 1, 5), Direction::Right, (0, 5)),
+            ((5, 0), Direction::Up, (5, height - 1)),
+            ((5, height - 1), Direction::Down, (5, 0)),
+        ];
+        for (from, direction, to) in &wraps {
+            let position = GridPosition::new_from_move((*from).into(), *direction, GRID_SIZE);
+            assert_eq!(position, (*to).into());
+        }
+    }
+
+    #[test]
+    fn only_the_wrapping_axes_wrap_around() {
+        let size = (4, 4);
+        let edges = [
+            (EdgeBehavior::Wrap, true, true),
+            (EdgeBehavior::Wall, false, false),
+            (EdgeBehavior::WrapX, true, false),
+            (EdgeBehavior::WrapY, false, true),
+        ];
+        for &(edges, wraps_x, wraps_y) in &edges {
+            let moves = [
+                ((0, 1), Direction::Left, (3, 1), wraps_x),
+                ((3, 1), Direction::Right, (0, 1), wraps_x),
+                ((1, 0), Direction::Up, (1, 3), wraps_y),
+                ((1, 3), Direction::Down, (1, 0), wraps_y),
+            ];
+            for &(from, direction, to, wraps) in &moves {
+                let position = GridPosition::try_move(from.into(), direction, size, edges);
+                assert_eq!(position, Some(to.into()).filter(|_| wraps), "{:?}", edges);
+            }
+            // and moving inside the board is always fine
+            let inside = GridPosition::try_move((1, 1).into(), Direction::Up, size, edges);
+            assert_eq!(inside, Some(GridPosition::new(1, 0)));
+        }
+        assert_eq!(
+            "wrap-x".parse::<EdgeBehavior>().unwrap(),
+            EdgeBehavior::WrapX
+        );
+        assert_eq!(
+            "wrap-y".parse::<EdgeBehavior>().unwrap(),
+            EdgeBehavior::WrapY
+        );
+    }
+
+    #[test]
+    fn moving_inside_the_board_moves_one_cell() {
+        let from = GridPosition::new(5, 5);
+        let moves = [
+            (Direction::Up, (5, 4)),
+            (Direction::Down, (5, 6)),
+            (Direction::Left, (4, 5)),
+            (Direction::Right, (6, 5)),
+        ];
+        for (direction, to) in &moves {
+            let position = GridPosition::new_from_move(from, *direction, GRID_SIZE);
+            assert_eq!(position, (*to).into());
+        }
+    }
+
+    #[test]
+    fn positions_are_scaled_by_the_cell_size() {
+        let position = GridPosition::new(3, 2);
+        assert_eq!(position.to_point((32, 32)), Point2 { x: 96.0, y: 64.0 });
+        assert_eq!(position.to_point((48, 16)), Point2 { x: 144.0, y: 32.0 });
+    }
+
+    #[test]
+    fn sprites_fill_rectangular_cells() {
+        let sprite = PositionedSprite::new(Sprite::Rabit, GridPosition::new(3, 2));
+        let param = sprite.to_draw_param((48, 24));
+        assert_eq!((param.dest.x, param.dest.y), (3.0 * 48.0, 2.0 * 24.0));
+        assert_eq!((param.scale.x, param.scale.y), (1.5, 0.75));
+        let scaled = sprite.scale_draw_param(param, (48, 24), 2.0);
+        assert_eq!((scaled.dest.x, scaled.dest.y), (144.0 - 24.0, 48.0 - 12.0));
+    }
+
+    #[test]
+    fn sprites_slide_towards_a_direction() {
+        let sprite = PositionedSprite::new(Sprite::Rabit, GridPosition::new(3, 2));
+        let dest = |param: DrawParam| (param.dest.x, param.dest.y);
+        assert_eq!(
+            dest(sprite.to_draw_param_towards((32, 32), Direction::Left, 0.0)),
+            (96.0, 64.0)
+        );
+        assert_eq!(
+            dest(sprite.to_draw_param_towards((32, 32), Direction::Left, 0.5)),
+            (80.0, 64.0)
+        );
+        assert_eq!(
+            dest(sprite.to_draw_param_towards((32, 32), Direction::Down, 0.25)),
+            (96.0, 72.0)
+        );
+    }
+
+    #[test]
+    fn easing_keeps_the_ends_of_the_slide() {
+        for easing in &[Easing::Linear, Easing::EaseInOut] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(0.5), 0.5);
+            assert_eq!(easing.apply(1.0), 1.0);
+            let slide: Vec<f32> = (0..=10)
+                .map(|step| easing.apply(step as f32 / 10.0))
+                .collect();
+            assert!(slide.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+        // easing in and out starts slower, and ends slower as well
+        assert!(Easing::EaseInOut.apply(0.1) < 0.1);
+        assert!(Easing::EaseInOut.apply(0.9) > 0.9);
+        assert_eq!("ease-in-out".parse::<Easing>().unwrap(), Easing::EaseInOut);
+    }
+
+    #[test]
+    fn update_segment_is_clamped() {
+        let interval = Duration::from_millis(100);
+        assert_eq!(update_segment(Duration::from_millis(0), interval), 0.0);
+        assert_eq!(update_segment(Duration::from_millis(50), interval), 0.5);
+        assert_eq!(update_segment(Duration::from_millis(5000), interval), 1.0);
+    }
+
+    #[test]
+    fn resume_countdown_counts_down_to_one() {
+        let at = |millis| resume_countdown_number(Duration::from_millis(millis));
+        assert_eq!(at(0), Some(3));
+        assert_eq!(at(499), Some(3));
+        assert_eq!(at(500), Some(2));
+        assert_eq!(at(1499), Some(1));
+        assert_eq!(at(RESUME_COUNTDOWN_MILLIS), None);
+    }
+
+    #[test]
+    fn combo_multiplier_is_capped() {
+        assert_eq!(combo_multiplier(0), 1);
+        assert_eq!(combo_multiplier(1), 1);
+        assert_eq!(combo_multiplier(3), 3);
+        assert_eq!(combo_multiplier(100), MAX_COMBO_MULTIPLIER);
+    }
+
+    #[test]
+    fn eat_pulse_shrinks_back() {
+        assert_eq!(eat_pulse_scale(Duration::default()), 1.0 + EAT_PULSE_SCALE);
+        let halfway = eat_pulse_scale(Duration::from_millis(EAT_PULSE_MILLIS / 2));
+        assert!((halfway - (1.0 + EAT_PULSE_SCALE / 2.0)).abs() < 1e-6);
+        assert_eq!(
+            eat_pulse_scale(Duration::from_millis(EAT_PULSE_MILLIS)),
+            1.0
+        );
+        assert_eq!(eat_pulse_scale(Duration::from_secs(10)), 1.0);
+    }
+
+    #[test]
+    fn scaling_a_sprite_keeps_it_centered() {
+        let sprite = PositionedSprite::new(Sprite::Rabit, (1, 2).into());
+        let param = sprite.to_draw_param((32, 32));
+        let scaled = sprite.scale_draw_param(param, (32, 32), 1.5);
+        assert_eq!(scaled.scale.x, param.scale.x * 1.5);
+        assert_eq!(scaled.dest.x, 32.0 - 8.0);
+        assert_eq!(scaled.dest.y, 64.0 - 8.0);
+    }
+
+    #[test]
+    fn only_long_gaps_are_stalls() {
+        let interval = Duration::from_millis(100);
+        assert!(!is_stalled(Duration::from_millis(150), interval));
+        assert!(!is_stalled(interval * MAX_STALLED_UPDATES, interval));
+        assert!(is_stalled(Duration::from_secs(5), interval));
     }
 }
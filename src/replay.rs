@@ -0,0 +1,145 @@
+use crate::{game::*, primitives::*};
+use ggez::{filesystem, Context};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The name of the file (inside the user data directory)
+/// that the last run is recorded to.
+const REPLAY_FILE: &str = "replay.json";
+
+/// A single turn that a player made during a run.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Input {
+    /// The tick (see `Game::tick`) that the turn was made before.
+    pub tick: u64,
+    pub player: Player,
+    pub direction: Direction,
+}
+
+/// A recording of a run, which can be replayed later.
+///
+/// The seed and the turns of the players are all it takes to reproduce a run,
+/// as long as it's replayed with the same configuration (e.g. the same board).
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Recording {
+    /// The seed of the random number generator of the run.
+    pub seed: u64,
+    /// The turns of the players, in the order they were made.
+    pub inputs: Vec<Input>,
+}
+
+impl Recording {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Returns the path of the replay file in the user data directory.
+    pub fn path(ctx: &Context) -> PathBuf {
+        filesystem::user_data_dir(ctx).join(REPLAY_FILE)
+    }
+
+    /// Feeds the turns that were made before the current tick into the `Game`
+    /// (which should have been created with the recorded seed).
+    pub fn replay_into(&self, game: &mut Game) {
+        let tick = game.tick();
+        let start = self.inputs.partition_point(|input| input.tick < tick);
+        for input in self.inputs[start..]
+            .iter()
+            .take_while(|input| input.tick == tick)
+        {
+            // the recorded turns were all valid, so this can't fail
+            let _ = game.set_player_direction(input.player, input.direction);
+        }
+    }
+
+    /// Loads a recording from the given file.
+    pub fn load_from(path: &Path) -> SnakeResult<Self> {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Saves the recording to the given file, replacing any previous one.
+    pub fn save_to(&self, path: &Path) -> SnakeResult {
+        let contents = serde_json::to_string(self)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::*;
+
+    /// The most ticks that a run of the tests lasts.
+    const MAX_TICKS: u64 = 500;
+
+    /// Helper function that plays a run with the given turns (one every few
+    /// ticks, until it ends or `MAX_TICKS`), returning the finished `Game`.
+    fn play(config: &GameConfig, turns: &[Direction]) -> Game {
+        let mut game = Game::new(config);
+        let mut turns = turns.iter().cycle();
+        while game.tick() < MAX_TICKS
+            && matches!(game.step_logic(), StepResult::Moved | StepResult::Ate(_))
+        {
+            if game.tick().is_multiple_of(3) {
+                let _ = game.set_direction(*turns.next().unwrap());
+            }
+        }
+        game
+    }
+
+    #[test]
+    fn replaying_a_recording_reproduces_the_run() {
+        let config = GameConfig {
+            seed: Some(7),
+            ..GameConfig::default()
+        };
+        let turns = [Direction::Down, Direction::Right, Direction::Up];
+        let played = play(&config, &turns);
+        let recording = played.recording().unwrap().clone();
+        assert!(!recording.inputs.is_empty());
+
+        let mut replayed = Game::new(&config);
+        while replayed.tick() < MAX_TICKS {
+            recording.replay_into(&mut replayed);
+            if !matches!(
+                replayed.step_logic(),
+                StepResult::Moved | StepResult::Ate(_)
+            ) {
+                break;
+            }
+        }
+        assert_eq!(replayed.tick(), played.tick());
+        assert_eq!(replayed.score(), played.score());
+        assert_eq!(replayed.recording(), Some(&recording));
+    }
+
+    #[test]
+    fn recording_survives_a_round_trip() {
+        let path = std::env::temp_dir().join(format!(
+            "snake-rs-test-{}-replay-round-trip.json",
+            std::process::id()
+        ));
+        let recording = Recording {
+            seed: 42,
+            inputs: vec![Input {
+                tick: 3,
+                player: Player::Two,
+                direction: Direction::Up,
+            }],
+        };
+        recording.save_to(&path).unwrap();
+        assert_eq!(Recording::load_from(&path).unwrap(), recording);
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::game::*;
+use ggez::input::gamepad::gilrs::{
+    ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Repeat, Replay, Ticks},
+    EventType, GamepadId, Gilrs,
+};
+use log::warn;
+
+/// How long (in milliseconds) the gamepad rumbles for after eating food.
+const LIGHT_RUMBLE_MILLIS: u32 = 80;
+/// How long (in milliseconds) the gamepad rumbles for after dying.
+const STRONG_RUMBLE_MILLIS: u32 = 300;
+
+/// How hard the gamepad rumbles in response to something happening in the game.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Strength {
+    /// A short buzz of the weak motor (e.g. after eating food).
+    Light,
+    /// A longer shake of the strong motor (e.g. after dying).
+    Strong,
+}
+
+impl Strength {
+    /// Returns how hard the gamepad should rumble after a step
+    /// of the game (`None` if it shouldn't rumble at all).
+    fn for_result(result: StepResult) -> Option<Self> {
+        match result {
+            StepResult::Moved | StepResult::TimeUp => None,
+            StepResult::Ate(_) | StepResult::Won => Some(Strength::Light),
+            StepResult::Died
+            | StepResult::PlayerLost(_)
+            | StepResult::Draw
+            | StepResult::Respawned(_) => Some(Strength::Strong),
+        }
+    }
+
+    /// Returns the force feedback effect of this strength.
+    fn effect(self) -> (BaseEffectType, u32) {
+        match self {
+            Strength::Light => (
+                BaseEffectType::Weak {
+                    magnitude: u16::MAX / 2,
+                },
+                LIGHT_RUMBLE_MILLIS,
+            ),
+            Strength::Strong => (
+                BaseEffectType::Strong {
+                    magnitude: u16::MAX,
+                },
+                STRONG_RUMBLE_MILLIS,
+            ),
+        }
+    }
+}
+
+/// Rumbles the gamepad that the player is using (if it supports it).
+///
+/// `ggez` doesn't give access to the force feedback of its own gamepad context,
+/// so this keeps a separate one, which also tracks the gamepad that was used
+/// last. Just like with the `Sounds`, a missing gamepad (or one that can't
+/// rumble) is simply skipped, and errors are reported and swallowed.
+pub struct Rumble {
+    gilrs: Option<Gilrs>,
+    /// The gamepad that a button or an axis was last used on.
+    gamepad: Option<GamepadId>,
+    /// The effect that's currently playing (which stops once it's dropped).
+    effect: Option<Effect>,
+}
+
+impl Rumble {
+    pub fn new() -> Self {
+        let gilrs = Gilrs::new()
+            .map_err(|error| warn!("Failed to initialize the gamepad rumble: {}", error))
+            .ok();
+        Self {
+            gilrs,
+            gamepad: None,
+            effect: None,
+        }
+    }
+
+    /// Keeps track of the gamepad that's being used, which should be called every frame.
+    pub fn poll(&mut self) {
+        let gilrs = match &mut self.gilrs {
+            Some(gilrs) => gilrs,
+            None => return,
+        };
+        while let Some(event) = gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(..) | EventType::AxisChanged(..) => {
+                    self.gamepad = Some(event.id)
+                }
+                EventType::Disconnected if self.gamepad == Some(event.id) => self.gamepad = None,
+                _ => {}
+            }
+        }
+    }
+
+    /// Rumbles the gamepad in response to what happened in a step of the game.
+    ///
+    /// Returns whether the rumble has actually started.
+    pub fn play_for(&mut self, result: StepResult) -> bool {
+        match Strength::for_result(result) {
+            Some(strength) => self.play(strength),
+            None => false,
+        }
+    }
+
+    /// Helper function that rumbles the gamepad with the given strength.
+    fn play(&mut self, strength: Strength) -> bool {
+        let (gilrs, gamepad) = match (&mut self.gilrs, self.gamepad) {
+            (Some(gilrs), Some(gamepad)) => (gilrs, gamepad),
+            _ => return false,
+        };
+        if !gilrs
+            .connected_gamepad(gamepad)
+            .is_some_and(|gamepad| gamepad.is_ff_supported())
+        {
+            return false;
+        }
+        let (kind, millis) = strength.effect();
+        let play_for = Ticks::from_ms(millis);
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind,
+                scheduling: Replay {
+                    play_for,
+                    ..Replay::default()
+                },
+                ..BaseEffect::default()
+            })
+            .gamepads(&[gamepad])
+            .repeat(Repeat::For(play_for))
+            .finish(gilrs)
+            .and_then(|effect| effect.play().map(|()| effect));
+        match effect {
+            Ok(effect) => {
+                self.effect = Some(effect);
+                true
+            }
+            Err(error) => {
+                warn!("Failed to rumble the gamepad: {}", error);
+                false
+            }
+        }
+    }
+}
+
+impl Default for Rumble {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eating_rumbles_lightly_and_dying_strongly() {
+        assert_eq!(Strength::for_result(StepResult::Moved), None);
+        assert_eq!(Strength::for_result(StepResult::TimeUp), None);
+        assert_eq!(
+            Strength::for_result(StepResult::Ate(1)),
+            Some(Strength::Light)
+        );
+        assert_eq!(
+            Strength::for_result(StepResult::Died),
+            Some(Strength::Strong)
+        );
+        assert_eq!(
+            Strength::for_result(StepResult::PlayerLost(Player::Two)),
+            Some(Strength::Strong)
+        );
+        assert_eq!(
+            Strength::for_result(StepResult::Respawned(Player::One)),
+            Some(Strength::Strong)
+        );
+    }
+
+    #[test]
+    fn no_gamepad_is_skipped() {
+        let mut rumble = Rumble {
+            gilrs: None,
+            gamepad: None,
+            effect: None,
+        };
+        rumble.poll();
+        assert!(!rumble.play_for(StepResult::Died));
+    }
+}
@@ -0,0 +1,160 @@
+use crate::{board::*, entities::*, primitives::*, state::*};
+use ggez::{filesystem, Context};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// The name of the file (inside the user data directory)
+/// that the saved game is stored in.
+const SAVE_FILE: &str = "save.json";
+
+/// Checks whether the current run can be saved, in the given `GameState` and
+/// while a recording is being replayed (`replaying`) or not.
+///
+/// Only a run that's still going (playing or paused) can be saved, and a replay
+/// can't be, since it's already saved as a recording.
+pub fn check_can_save(state: GameState, replaying: bool) -> SnakeResult {
+    if replaying {
+        return Err(SnakeError::SaveError("A replay can't be saved".to_owned()));
+    }
+    if state != GameState::Playing && state != GameState::Paused {
+        return Err(SnakeError::SaveError(
+            "Only a game in progress can be saved".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// Checks whether a saved run can be loaded while a recording is being replayed
+/// (`replaying`) or not, which it can't: the recorded turns would go on steering
+/// the loaded run (see `Recording::replay_into`).
+pub fn check_can_load(replaying: bool) -> SnakeResult {
+    if replaying {
+        return Err(SnakeError::SaveError(
+            "A saved game can't be loaded during a replay".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+/// A snapshot of a run in progress, which can be saved and resumed later.
+///
+/// Only the state of the run itself is saved: everything else
+/// (e.g. the sprites and sounds) is already loaded by the `Game`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedGame {
+    /// The board that the run was played on (a save can only
+    /// be resumed on the same board).
+    pub board: Board,
+    /// The snake of each player (and so, a save can only be
+    /// resumed with the same number of players).
+    pub snakes: Vec<Snake>,
+    pub food: Food,
+    /// The rest of the normal foods, if there are more than one
+    /// (saves from before there could be have none).
+    #[serde(default)]
+    pub extra_foods: Vec<Food>,
+    pub bonus_food: Option<Food>,
+    #[serde(default)]
+    pub poison_food: Option<Food>,
+    /// The score of each player.
+    pub scores: Vec<u32>,
+    /// How long the run was played for (saves from before
+    /// it was kept have been played for no time at all).
+    #[serde(default)]
+    pub elapsed: Duration,
+}
+
+impl SavedGame {
+    /// Returns the path of the save file in the user data directory.
+    pub fn path(ctx: &Context) -> PathBuf {
+        filesystem::user_data_dir(ctx).join(SAVE_FILE)
+    }
+
+    /// Loads a saved game from the given file.
+    pub fn load_from(path: &Path) -> SnakeResult<Self> {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// Saves the game to the given file, replacing any previous save.
+    pub fn save_to(&self, path: &Path) -> SnakeResult {
+        let contents = serde_json::to_string(self)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Helper function that creates a unique, non-existent path for a test.
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "snake-rs-test-{}-{}.json",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn saved_game_survives_a_round_trip() {
+        let path = temp_path("save-round-trip");
+        let board = Board::default();
+        let mut snake = Snake::new((5, 5).into(), Direction::Right, START_LENGTH, &board);
+        snake.set_direction(Direction::Down).unwrap();
+        let saved = SavedGame {
+            board: board.clone(),
+            snakes: vec![snake],
+            food: Food::new((6, 5).into()),
+            extra_foods: Vec::new(),
+            bonus_food: Some(Food::new_bonus((1, 1).into())),
+            poison_food: None,
+            scores: vec![7],
+            elapsed: Duration::from_secs(12),
+        };
+        saved.save_to(&path).unwrap();
+
+        let mut loaded = SavedGame::load_from(&path).unwrap();
+        assert_eq!(loaded.board, board);
+        assert_eq!(loaded.scores, vec![7]);
+        assert_eq!(loaded.elapsed, Duration::from_secs(12));
+        assert_eq!(loaded.food.position(), GridPosition::new(6, 5));
+        assert_eq!(
+            loaded.bonus_food.map(|food| food.points()),
+            Some(POINTS_PER_FOOD * BONUS_FOOD_MULTIPLIER)
+        );
+        // the queued turn was saved along with the snake
+        loaded.snakes[0].update(None, &board, &[]);
+        assert_eq!(loaded.snakes[0].head_position(), GridPosition::new(5, 6));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn replays_cant_be_saved_or_loaded_over() {
+        assert!(check_can_save(GameState::Playing, false).is_ok());
+        assert!(check_can_save(GameState::Paused, false).is_ok());
+        assert!(check_can_save(GameState::GameOver, false).is_err());
+        assert!(check_can_save(GameState::Playing, true).is_err());
+        assert!(check_can_load(false).is_ok());
+        assert!(check_can_load(true).is_err());
+    }
+
+    #[test]
+    fn missing_or_corrupt_save_is_an_error() {
+        let path = temp_path("save-corrupt");
+        assert!(SavedGame::load_from(&path).is_err());
+        fs::write(&path, "{ \"scores\": [3] }").unwrap();
+        assert!(SavedGame::load_from(&path).is_err());
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::primitives::*;
+use ggez::{filesystem, Context};
+use log::warn;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The name of the file (inside the user data directory)
+/// that the high score is stored in.
+const HIGH_SCORE_FILE: &str = "high-score";
+/// The name of the file (inside the user data directory)
+/// that the scoreboard is stored in.
+const SCOREBOARD_FILE: &str = "scoreboard.json";
+/// The number of scores that the scoreboard keeps.
+pub const SCOREBOARD_SIZE: usize = 10;
+/// The number of characters in the initials of a scoreboard entry.
+pub const INITIALS_LENGTH: usize = 3;
+
+/// Keeps track of the best score ever reached,
+/// and persists it to disk between runs.
+pub struct HighScore {
+    path: PathBuf,
+    best: u32,
+}
+
+impl HighScore {
+    /// Loads the high score from the user data directory.
+    pub fn load(ctx: &Context) -> Self {
+        Self::load_from(filesystem::user_data_dir(ctx).join(HIGH_SCORE_FILE))
+    }
+
+    /// Loads the high score from the given file.
+    ///
+    /// A missing file simply means that no high score was set yet,
+    /// and a corrupt one is reported and reset, so both default to `0`.
+    pub fn load_from(path: PathBuf) -> Self {
+        let best = match fs::read_to_string(&path) {
+            Ok(contents) => contents.trim().parse().unwrap_or_else(|error| {
+                warn!(
+                    "Corrupt high score file `{}` ({}), resetting it",
+                    path.display(),
+                    error
+                );
+                0
+            }),
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => 0,
+            Err(error) => {
+                warn!(
+                    "Failed to read high score file `{}`: {}",
+                    path.display(),
+                    error
+                );
+                0
+            }
+        };
+        Self { path, best }
+    }
+
+    pub fn best(&self) -> u32 {
+        self.best
+    }
+
+    /// Submits a finished game's score, saving it if it beats
+    /// the current high score.
+    ///
+    /// Returns whether a new high score was set.
+    pub fn submit(&mut self, score: u32) -> bool {
+        if score <= self.best {
+            return false;
+        }
+        self.best = score;
+        if let Err(error) = self.save() {
+            warn!(
+                "Failed to save high score to `{}`: {}",
+                self.path.display(),
+                error
+            );
+        }
+        true
+    }
+
+    /// Helper function that writes the high score to disk.
+    fn save(&self) -> io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, self.best.to_string())
+    }
+}
+
+/// Keeps track of the best scores (`SCOREBOARD_SIZE` of them) along
+/// with the initials of whoever reached them, and persists them
+/// to disk between runs.
+pub struct Scoreboard {
+    path: PathBuf,
+    /// The initials and the score of each entry, from the best score down.
+    entries: Vec<(String, u32)>,
+}
+
+impl Scoreboard {
+    /// Loads the scoreboard from the user data directory.
+    pub fn load(ctx: &Context) -> Self {
+        Self::load_from(filesystem::user_data_dir(ctx).join(SCOREBOARD_FILE))
+    }
+
+    /// Loads the scoreboard from the given file.
+    ///
+    /// A missing file simply means that no scores were set yet,
+    /// and a corrupt one is reported and reset, so both start empty.
+    pub fn load_from(path: PathBuf) -> Self {
+        let mut entries = match Self::read_entries(&path) {
+            Ok(entries) => entries,
+            Err(SnakeError::Io(ref error)) if error.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(error) => {
+                warn!(
+                    "Failed to load scoreboard file `{}` ({}), resetting it",
+                    path.display(),
+                    error
+                );
+                Vec::new()
+            }
+        };
+        // the file could've been edited by hand
+        entries.sort_by(|(_, a), (_, b)| b.cmp(a));
+        entries.truncate(SCOREBOARD_SIZE);
+        Self { path, entries }
+    }
+
+    /// Helper function that reads and parses the scoreboard file.
+    fn read_entries(path: &Path) -> SnakeResult<Vec<(String, u32)>> {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    /// The initials and the score of each entry, from the best score down.
+    pub fn entries(&self) -> &[(String, u32)] {
+        &self.entries
+    }
+
+    /// Checks if the given score would make it onto the scoreboard.
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && (self.entries.len() < SCOREBOARD_SIZE
+                || self.entries.last().is_some_and(|(_, last)| score > *last))
+    }
+
+    /// Inserts a finished game's score (if it qualifies), saving the scoreboard.
+    ///
+    /// Returns the index of the new entry, which comes after
+    /// any earlier entries with the same score.
+    pub fn insert(&mut self, initials: &str, score: u32) -> Option<usize> {
+        if !self.qualifies(score) {
+            return None;
+        }
+        let index = self.entries.partition_point(|(_, best)| *best >= score);
+        self.entries.insert(index, (initials.to_owned(), score));
+        self.entries.truncate(SCOREBOARD_SIZE);
+        if let Err(error) = self.save() {
+            warn!(
+                "Failed to save scoreboard to `{}`: {}",
+                self.path.display(),
+                error
+            );
+        }
+        Some(index)
+    }
+
+    /// Helper function that writes the scoreboard to disk.
+    fn save(&self) -> SnakeResult {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let contents = serde_json::to_string(&self.entries)?;
+        fs::write(&self.path, contents)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Helper function that creates a unique, non-existent path for a test.
+    fn temp_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("snake-rs-test-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn missing_file_defaults_to_zero() {
+        let high_score = HighScore::load_from(temp_path("missing"));
+        assert_eq!(high_score.best(), 0);
+    }
+
+    #[test]
+    fn corrupt_file_resets_to_zero() {
+        let path = temp_path("corrupt");
+        fs::write(&path, "not a number").unwrap();
+        assert_eq!(HighScore::load_from(path.clone()).best(), 0);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn only_better_scores_are_persisted() {
+        let path = temp_path("persisted");
+        let mut high_score = HighScore::load_from(path.clone());
+        assert!(high_score.submit(12));
+        assert!(!high_score.submit(7));
+        assert_eq!(HighScore::load_from(path.clone()).best(), 12);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn missing_or_corrupt_scoreboard_is_empty() {
+        assert!(Scoreboard::load_from(temp_path("missing-scoreboard"))
+            .entries()
+            .is_empty());
+        let path = temp_path("corrupt-scoreboard");
+        fs::write(&path, "{ not json").unwrap();
+        assert!(Scoreboard::load_from(path.clone()).entries().is_empty());
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn scoreboard_keeps_the_best_scores_in_order() {
+        let path = temp_path("scoreboard");
+        let mut scoreboard = Scoreboard::load_from(path.clone());
+        assert!(!scoreboard.qualifies(0));
+        for score in 1..=SCOREBOARD_SIZE as u32 {
+            scoreboard.insert("AAA", score * 10);
+        }
+        assert!(!scoreboard.qualifies(10));
+        assert_eq!(scoreboard.insert("BBB", 5), None);
+        // ties go after the entries that reached the score first
+        assert_eq!(scoreboard.insert("CCC", 50), Some(6));
+        let loaded = Scoreboard::load_from(path.clone());
+        assert_eq!(loaded.entries().len(), SCOREBOARD_SIZE);
+        assert_eq!(loaded.entries()[0], ("AAA".to_owned(), 100));
+        assert_eq!(loaded.entries()[6], ("CCC".to_owned(), 50));
+        assert_eq!(loaded.entries()[9], ("AAA".to_owned(), 20));
+        let _ = fs::remove_file(path);
+    }
+}
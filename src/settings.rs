@@ -0,0 +1,221 @@
+use crate::primitives::*;
+use ggez::{filesystem, graphics::FilterMode, Context};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The name of the file (inside the user config directory)
+/// that the settings are stored in.
+const SETTINGS_FILE: &str = "settings.toml";
+
+/// The player's preferences, which are persisted to disk between runs.
+///
+/// Any setting that is missing from the file falls back to its default,
+/// so that older files keep working as new settings are added.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Whether the sound effects are played.
+    pub audio_enabled: bool,
+    /// Whether the sprites are scaled smoothly, rather than with
+    /// crisp (nearest neighbor) pixels, which suits pixel art better.
+    pub smooth_filtering: bool,
+    /// Whether the lines between the cells of the board are drawn.
+    pub show_grid: bool,
+    /// The name of the theme picked in the settings menu, which replaces
+    /// the default one (but not one given on the command line).
+    pub theme: Option<String>,
+    /// The name of the difficulty picked in the settings menu, which
+    /// replaces the default one (but not one given on the command line).
+    pub difficulty: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            audio_enabled: true,
+            smooth_filtering: false,
+            show_grid: false,
+            theme: None,
+            difficulty: None,
+        }
+    }
+}
+
+/// The options of the in-game settings menu, in the order they're listed.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SettingsOption {
+    Sound,
+    Grid,
+    Theme,
+    Difficulty,
+}
+
+impl SettingsOption {
+    pub const ALL: [SettingsOption; 4] = [
+        SettingsOption::Sound,
+        SettingsOption::Grid,
+        SettingsOption::Theme,
+        SettingsOption::Difficulty,
+    ];
+
+    /// The name of the option, as it's shown in the menu.
+    pub fn label(self) -> &'static str {
+        match self {
+            SettingsOption::Sound => "Sound",
+            SettingsOption::Grid => "Grid lines",
+            SettingsOption::Theme => "Theme",
+            SettingsOption::Difficulty => "Difficulty",
+        }
+    }
+}
+
+/// The cursor of the in-game settings menu (see `SettingsOption`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct SettingsMenu {
+    cursor: usize,
+}
+
+impl SettingsMenu {
+    /// The option that the cursor is on.
+    pub fn selected(self) -> SettingsOption {
+        SettingsOption::ALL[self.cursor]
+    }
+
+    /// Moves the cursor up (or down) the list of options, wrapping around its ends.
+    pub fn move_cursor(&mut self, up: bool) {
+        let count = SettingsOption::ALL.len();
+        self.cursor = if up {
+            (self.cursor + count - 1) % count
+        } else {
+            (self.cursor + 1) % count
+        };
+    }
+}
+
+impl Settings {
+    /// Returns the filter mode that the sprites are scaled with.
+    pub fn filter_mode(&self) -> FilterMode {
+        if self.smooth_filtering {
+            FilterMode::Linear
+        } else {
+            FilterMode::Nearest
+        }
+    }
+
+    /// Returns the path of the settings file in the user config directory.
+    pub fn path(ctx: &Context) -> PathBuf {
+        filesystem::user_config_dir(ctx).join(SETTINGS_FILE)
+    }
+
+    /// Loads the settings from the given file.
+    ///
+    /// A missing file simply means that nothing was changed yet,
+    /// and a corrupt one is reported and reset, so both use the defaults.
+    pub fn load_from(path: &Path) -> Self {
+        match Self::read_from(path) {
+            Ok(settings) => settings,
+            Err(SnakeError::Io(ref error)) if error.kind() == io::ErrorKind::NotFound => {
+                Self::default()
+            }
+            Err(error) => {
+                warn!(
+                    "Failed to load settings file `{}` ({}), using the defaults",
+                    path.display(),
+                    error
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// Helper function that reads and parses the settings file.
+    fn read_from(path: &Path) -> SnakeResult<Self> {
+        let contents = fs::read_to_string(path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+
+    /// Saves the settings to the given file, reporting (but otherwise
+    /// ignoring) any failure.
+    pub fn save_to(&self, path: &Path) {
+        if let Err(error) = self.write_to(path) {
+            warn!("Failed to save settings to `{}`: {}", path.display(), error);
+        }
+    }
+
+    /// Helper function that writes the settings file.
+    fn write_to(&self, path: &Path) -> SnakeResult {
+        let contents = toml::to_string(self)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Helper function that creates a unique, non-existent path for a test.
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "snake-rs-test-{}-{}.toml",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn missing_or_corrupt_file_uses_the_defaults() {
+        let path = temp_path("settings-corrupt");
+        assert_eq!(Settings::load_from(&path), Settings::default());
+        fs::write(&path, "audio_enabled = 'maybe'").unwrap();
+        assert_eq!(Settings::load_from(&path), Settings::default());
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn older_files_get_the_new_settings_defaults() {
+        let path = temp_path("settings-older");
+        fs::write(&path, "audio_enabled = false").unwrap();
+        let settings = Settings::load_from(&path);
+        assert!(!settings.audio_enabled);
+        assert!(matches!(settings.filter_mode(), FilterMode::Nearest));
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn menu_cursor_wraps_around() {
+        let mut menu = SettingsMenu::default();
+        assert_eq!(menu.selected(), SettingsOption::Sound);
+        menu.move_cursor(false);
+        assert_eq!(menu.selected(), SettingsOption::Grid);
+        menu.move_cursor(true);
+        menu.move_cursor(true);
+        assert_eq!(menu.selected(), SettingsOption::Difficulty);
+        menu.move_cursor(false);
+        assert_eq!(menu.selected(), SettingsOption::Sound);
+    }
+
+    #[test]
+    fn settings_survive_a_round_trip() {
+        let path = temp_path("settings-round-trip");
+        let settings = Settings {
+            audio_enabled: false,
+            smooth_filtering: true,
+            show_grid: true,
+            theme: Some("dark".to_owned()),
+            difficulty: None,
+        };
+        settings.save_to(&path);
+        assert_eq!(Settings::load_from(&path), settings);
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,157 @@
+/// The different states that the game can be in.
+///
+/// All of the transitions between the states go through
+/// the methods below, which ignore transitions that don't
+/// make sense in the current state (e.g. pausing a finished game).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameState {
+    /// The start menu, shown before the game begins.
+    Menu,
+    /// The snake is moving.
+    Playing,
+    /// The game was paused by the player.
+    Paused,
+    /// The snake died.
+    GameOver,
+    /// The snake filled the whole board.
+    Won,
+    /// The game ended with a score that made it onto the
+    /// `Scoreboard`, and the player is typing their initials.
+    EnteringInitials,
+    /// The best scores are shown (see `Scoreboard`).
+    Leaderboard,
+    /// The in-game settings menu is open (which pauses the game).
+    Settings,
+}
+
+impl GameState {
+    /// Checks if the game has ended, either by losing or by winning.
+    pub fn is_finished(self) -> bool {
+        match self {
+            GameState::GameOver | GameState::Won => true,
+            GameState::Menu
+            | GameState::Playing
+            | GameState::Paused
+            | GameState::EnteringInitials
+            | GameState::Leaderboard
+            | GameState::Settings => false,
+        }
+    }
+
+    /// Returns the state after the player starts the game from the menu.
+    pub fn start(self) -> Self {
+        match self {
+            GameState::Menu => GameState::Playing,
+            state => state,
+        }
+    }
+
+    /// Returns the state after the player pauses / unpauses the game.
+    pub fn toggle_pause(self) -> Self {
+        match self {
+            GameState::Playing => GameState::Paused,
+            GameState::Paused => GameState::Playing,
+            state => state,
+        }
+    }
+
+    /// Returns the state after the game ended, either by losing or by winning.
+    pub fn finish(self, won: bool) -> Self {
+        match self {
+            GameState::Playing if won => GameState::Won,
+            GameState::Playing => GameState::GameOver,
+            state => state,
+        }
+    }
+
+    /// Returns the state after the player opens / closes the settings menu,
+    /// which can only be opened during a game, and closes to the pause
+    /// screen (so that the player has a moment to get ready).
+    pub fn toggle_settings(self) -> Self {
+        match self {
+            GameState::Playing | GameState::Paused => GameState::Settings,
+            GameState::Settings => GameState::Paused,
+            state => state,
+        }
+    }
+
+    /// Returns the state after a finished game's score made it onto the `Scoreboard`.
+    pub fn enter_initials(self) -> Self {
+        match self {
+            GameState::GameOver | GameState::Won => GameState::EnteringInitials,
+            state => state,
+        }
+    }
+
+    /// Returns the state after the player opens / closes the leaderboard,
+    /// which can be opened from the menu or once the game is over (and
+    /// the initials were entered), and always closes back to the menu.
+    pub fn toggle_leaderboard(self) -> Self {
+        match self {
+            GameState::Menu
+            | GameState::GameOver
+            | GameState::Won
+            | GameState::EnteringInitials => GameState::Leaderboard,
+            GameState::Leaderboard => GameState::Menu,
+            state => state,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn menu_only_starts_the_game() {
+        assert_eq!(GameState::Menu.start(), GameState::Playing);
+        assert_eq!(GameState::Menu.toggle_pause(), GameState::Menu);
+        assert_eq!(GameState::Menu.finish(false), GameState::Menu);
+        assert_eq!(GameState::Paused.start(), GameState::Paused);
+    }
+
+    #[test]
+    fn pause_toggles_only_while_playing() {
+        assert_eq!(GameState::Playing.toggle_pause(), GameState::Paused);
+        assert_eq!(GameState::Paused.toggle_pause(), GameState::Playing);
+        assert_eq!(GameState::GameOver.toggle_pause(), GameState::GameOver);
+        assert_eq!(GameState::Won.toggle_pause(), GameState::Won);
+    }
+
+    #[test]
+    fn only_a_running_game_can_finish() {
+        assert_eq!(GameState::Playing.finish(false), GameState::GameOver);
+        assert_eq!(GameState::Playing.finish(true), GameState::Won);
+        assert_eq!(GameState::Paused.finish(false), GameState::Paused);
+        assert_eq!(GameState::GameOver.finish(true), GameState::GameOver);
+        assert!(GameState::Won.is_finished());
+        assert!(!GameState::Paused.is_finished());
+    }
+
+    #[test]
+    fn settings_open_during_a_game_and_close_paused() {
+        assert_eq!(GameState::Playing.toggle_settings(), GameState::Settings);
+        assert_eq!(GameState::Paused.toggle_settings(), GameState::Settings);
+        assert_eq!(GameState::Settings.toggle_settings(), GameState::Paused);
+        assert_eq!(GameState::Menu.toggle_settings(), GameState::Menu);
+        assert_eq!(GameState::Settings.toggle_pause(), GameState::Settings);
+        assert_eq!(GameState::Settings.finish(false), GameState::Settings);
+    }
+
+    #[test]
+    fn leaderboard_is_shown_between_games() {
+        assert_eq!(
+            GameState::GameOver.enter_initials(),
+            GameState::EnteringInitials
+        );
+        assert_eq!(GameState::Playing.enter_initials(), GameState::Playing);
+        assert_eq!(
+            GameState::EnteringInitials.toggle_leaderboard(),
+            GameState::Leaderboard
+        );
+        assert_eq!(GameState::Menu.toggle_leaderboard(), GameState::Leaderboard);
+        assert_eq!(GameState::Leaderboard.toggle_leaderboard(), GameState::Menu);
+        assert_eq!(GameState::Paused.toggle_leaderboard(), GameState::Paused);
+        assert_eq!(GameState::Leaderboard.start(), GameState::Leaderboard);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::primitives::*;
+use ggez::graphics::{self, Color};
+use std::str::FromStr;
+
+/// How much darker every other grass tile is on a checkerboard background.
+const CHECKERBOARD_SHADE: f32 = 0.88;
+
+/// The colors that the game is drawn with.
+///
+/// The sprites are tinted with these colors, so `graphics::WHITE`
+/// keeps the original colors of the spritesheet.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Theme {
+    pub name: &'static str,
+    /// The clear color (only visible around a letterboxed board).
+    pub background: Color,
+    /// The tint of the grass tiles.
+    pub grass: Color,
+    /// The tint of the snake (unless the player picked their own color).
+    pub snake: Color,
+    /// The tint of the normal food.
+    pub food: Color,
+    /// The shade that the snake fades into towards its tail, with the
+    /// gradient on (see `Theme::snake_gradient`), which is multiplied into its tint.
+    pub snake_tail: Color,
+}
+
+/// The tint of a `Snake`, which fades from its head to its tail.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Gradient {
+    pub head: Color,
+    pub tail: Color,
+}
+
+impl Gradient {
+    /// Creates a `Gradient` that's the same color all the way.
+    pub fn solid(color: Color) -> Self {
+        Self {
+            head: color,
+            tail: color,
+        }
+    }
+
+    /// Returns the color of the `Gradient` at the given `fraction`
+    /// of the way from the head (0.0) to the tail (1.0).
+    pub fn at(&self, fraction: f32) -> Color {
+        let fraction = fraction.clamp(0.0, 1.0);
+        let mix = |head: f32, tail: f32| head * (1.0 - fraction) + tail * fraction;
+        Color::new(
+            mix(self.head.r, self.tail.r),
+            mix(self.head.g, self.tail.g),
+            mix(self.head.b, self.tail.b),
+            mix(self.head.a, self.tail.a),
+        )
+    }
+}
+
+impl Theme {
+    /// Returns all of the available themes, starting with the default one.
+    pub fn presets() -> [Theme; 3] {
+        [
+            Theme {
+                name: "blue",
+                background: Color::from_rgb(40, 50, 130),
+                grass: graphics::WHITE,
+                snake: graphics::WHITE,
+                food: graphics::WHITE,
+                snake_tail: Color::from_rgb(110, 120, 160),
+            },
+            Theme {
+                name: "dark",
+                background: Color::from_rgb(10, 10, 15),
+                grass: Color::from_rgb(70, 70, 90),
+                snake: Color::from_rgb(200, 200, 220),
+                food: Color::from_rgb(255, 120, 120),
+                snake_tail: Color::from_rgb(90, 90, 110),
+            },
+            Theme {
+                name: "green",
+                background: Color::from_rgb(20, 60, 20),
+                grass: Color::from_rgb(150, 220, 120),
+                snake: Color::from_rgb(120, 255, 120),
+                food: graphics::WHITE,
+                snake_tail: Color::from_rgb(90, 150, 70),
+            },
+        ]
+    }
+
+    /// Returns the tint of the grass tile at the given position, which (on a
+    /// `checkerboard` background) is slightly darker on every other cell.
+    pub fn grass_at(&self, position: GridPosition, checkerboard: bool) -> Color {
+        if checkerboard && (position.x + position.y) % 2 == 1 {
+            Color::new(
+                self.grass.r * CHECKERBOARD_SHADE,
+                self.grass.g * CHECKERBOARD_SHADE,
+                self.grass.b * CHECKERBOARD_SHADE,
+                self.grass.a,
+            )
+        } else {
+            self.grass
+        }
+    }
+
+    /// Returns the gradient of a snake tinted with `color`, which fades into
+    /// the `snake_tail` shade of the theme towards its tail.
+    pub fn snake_gradient(&self, color: Color) -> Gradient {
+        Gradient {
+            head: color,
+            tail: Color::new(
+                color.r * self.snake_tail.r,
+                color.g * self.snake_tail.g,
+                color.b * self.snake_tail.b,
+                color.a * self.snake_tail.a,
+            ),
+        }
+    }
+
+    /// Returns the theme before this one (wrapping around to the last one).
+    pub fn previous(&self) -> Theme {
+        let presets = Self::presets();
+        let index = presets
+            .iter()
+            .position(|theme| theme.name == self.name)
+            .map_or(0, |index| (index + presets.len() - 1) % presets.len());
+        presets[index]
+    }
+
+    /// Returns the theme after this one (wrapping around to the first one).
+    pub fn next(&self) -> Theme {
+        let presets = Self::presets();
+        let index = presets
+            .iter()
+            .position(|theme| theme.name == self.name)
+            .map_or(0, |index| (index + 1) % presets.len());
+        presets[index]
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::presets()[0]
+    }
+}
+
+impl FromStr for Theme {
+    type Err = SnakeError;
+
+    fn from_str(s: &str) -> SnakeResult<Self> {
+        let presets = Self::presets();
+        presets
+            .iter()
+            .find(|theme| theme.name == s)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = presets.iter().map(|theme| theme.name).collect();
+                SnakeError::ArgumentError(format!(
+                    "Unknown theme `{}` (expected one of: {})",
+                    s,
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn themes_are_found_by_name() {
+        assert_eq!("dark".parse::<Theme>().unwrap().name, "dark");
+        assert_eq!("green".parse::<Theme>().unwrap().name, "green");
+        assert!("pink".parse::<Theme>().is_err());
+    }
+
+    #[test]
+    fn checkerboard_darkens_every_other_cell() {
+        let theme = Theme::default();
+        let (even, odd) = (GridPosition::new(1, 1), GridPosition::new(1, 2));
+        assert_eq!(theme.grass_at(even, true), theme.grass);
+        assert_ne!(theme.grass_at(odd, true), theme.grass);
+        assert_eq!(theme.grass_at(odd, false), theme.grass);
+    }
+
+    #[test]
+    fn gradients_fade_from_the_head_to_the_tail() {
+        let theme = Theme::default();
+        let gradient = theme.snake_gradient(graphics::WHITE);
+        assert_eq!(gradient.at(0.0), graphics::WHITE);
+        assert_eq!(gradient.at(1.0), theme.snake_tail);
+        let middle = gradient.at(0.5);
+        assert!(middle.r < 1.0 && middle.r > theme.snake_tail.r);
+        assert_eq!(Gradient::solid(theme.food).at(0.7), theme.food);
+    }
+
+    #[test]
+    fn cycling_visits_every_theme() {
+        let first = Theme::default();
+        let mut theme = first;
+        for _ in 1..Theme::presets().len() {
+            theme = theme.next();
+            assert_ne!(theme, first);
+        }
+        assert_eq!(theme.next(), first);
+        assert_eq!(first.next().previous(), first);
+        assert_eq!(first.previous(), theme);
+    }
+}